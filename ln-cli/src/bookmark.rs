@@ -0,0 +1,217 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use ln_lib::{Lightnovel, LightnovelChapter};
+use serde::{Deserialize, Serialize};
+
+use crate::{data_dir, write_file};
+
+const FINGERPRINT_WORDS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkKind {
+	Bookmark,
+	Highlight,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+	pub kind: MarkKind,
+	pub novel_title: String,
+	pub novel_url: String,
+	pub chapter_title: String,
+	pub chapter_url: String,
+	pub chapter_number: usize,
+	pub paragraph: usize,
+	pub fingerprint: String,
+	pub note: Option<String>,
+}
+
+impl Bookmark {
+	pub fn new(kind: MarkKind, ln: &Lightnovel, chapter: &LightnovelChapter, paragraph: usize, note: Option<String>) -> Self {
+		Self {
+			kind,
+			novel_title: ln.get_title().clone(),
+			novel_url: ln.get_url().clone(),
+			chapter_title: chapter.get_title().clone(),
+			chapter_url: chapter.get_url().clone(),
+			chapter_number: chapter.get_chapter_number(),
+			paragraph,
+			fingerprint: chapter.get(paragraph).map(|p| fingerprint(p)).unwrap_or_default(),
+			note,
+		}
+	}
+
+	/// Finds the paragraph this bookmark points to, even if the paragraph indices shifted since it was created
+	pub fn anchor(&self, chapter: &LightnovelChapter) -> Option<usize> {
+		if chapter.get(self.paragraph).map(|p| fingerprint(p) == self.fingerprint).unwrap_or(false) {
+			return Some(self.paragraph);
+		}
+
+		chapter
+			.iter()
+			.enumerate()
+			.filter(|(_, p)| fingerprint(p) == self.fingerprint)
+			.map(|(i, _)| i)
+			.min_by_key(|i| i.abs_diff(self.paragraph))
+	}
+}
+
+impl Display for Bookmark {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let kind = match self.kind {
+			MarkKind::Bookmark => "Bookmark",
+			MarkKind::Highlight => "Highlight",
+		};
+
+		write!(
+			f,
+			"[{}] {} | {} | paragraph {}",
+			kind,
+			self.novel_title,
+			self.chapter_title,
+			self.paragraph + 1
+		)?;
+
+		match &self.note {
+			Some(note) => write!(f, " | {}", note),
+			None => Ok(()),
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+	path: PathBuf,
+	list: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		let path = data_dir()?.join("bookmarks.json");
+
+		let list = match fs::read_to_string(&path) {
+			Ok(content) => match serde_json::from_str(&content) {
+				Ok(list) => list,
+				Err(err) => return Err(format!("There is a problem in the bookmarks {}: {}", path.display(), err).into()),
+			},
+			Err(err) if err.kind() == ErrorKind::NotFound => Vec::new(),
+			Err(err) => return Err(format!("Could not read the bookmarks {}: {}", path.display(), err).into()),
+		};
+
+		Ok(Self { path, list })
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		write_file(&self.path, &serde_json::to_string_pretty(&self.list)?)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &Bookmark> {
+		self.list.iter()
+	}
+
+	pub fn get(&self, chapter_url: &str, paragraph: usize, kind: MarkKind) -> Option<&Bookmark> {
+		self
+			.list
+			.iter()
+			.find(|b| b.chapter_url == chapter_url && b.paragraph == paragraph && b.kind == kind)
+	}
+
+	pub fn add(&mut self, bookmark: Bookmark) {
+		self.remove(&bookmark.chapter_url, bookmark.paragraph, bookmark.kind);
+		self.list.push(bookmark);
+	}
+
+	pub fn remove(&mut self, chapter_url: &str, paragraph: usize, kind: MarkKind) -> bool {
+		let len = self.list.len();
+		self
+			.list
+			.retain(|b| !(b.chapter_url == chapter_url && b.paragraph == paragraph && b.kind == kind));
+		len != self.list.len()
+	}
+
+	/// Moves the bookmarks of the chapter to their paragraph, returns if something was moved
+	pub fn reanchor(&mut self, chapter: &LightnovelChapter) -> bool {
+		let mut changed = false;
+
+		for bookmark in self.list.iter_mut().filter(|b| &b.chapter_url == chapter.get_url()) {
+			if let Some(paragraph) = bookmark.anchor(chapter) {
+				if paragraph != bookmark.paragraph {
+					bookmark.paragraph = paragraph;
+					changed = true;
+				}
+			}
+		}
+
+		changed
+	}
+}
+
+fn fingerprint(paragraph: &str) -> String {
+	paragraph
+		.split_whitespace()
+		.map(|word| {
+			word
+				.chars()
+				.filter(|c| c.is_alphanumeric())
+				.flat_map(char::to_lowercase)
+				.collect::<String>()
+		})
+		.filter(|word| !word.is_empty())
+		.take(FINGERPRINT_WORDS)
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chapter(paragraphs: &[&str]) -> LightnovelChapter {
+		let mut chapter = LightnovelChapter::new("Chapter 1".to_string(), "/novel/x/chapter-1".to_string(), 1);
+		chapter.extend(paragraphs.iter().map(|p| p.to_string()));
+		chapter
+	}
+
+	fn bookmark(chapter: &LightnovelChapter, paragraph: usize) -> Bookmark {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		Bookmark::new(MarkKind::Bookmark, &ln, chapter, paragraph, None)
+	}
+
+	#[test]
+	fn anchor_keeps_the_paragraph_if_it_still_matches() {
+		let chapter = chapter(&["One.", "Two.", "Three."]);
+		assert_eq!(bookmark(&chapter, 1).anchor(&chapter), Some(1));
+	}
+
+	#[test]
+	fn anchor_follows_a_shifted_paragraph() {
+		let old = chapter(&["One.", "Two.", "Three."]);
+		let new = chapter(&["Translator note.", "One.", "Two.", "Three."]);
+		assert_eq!(bookmark(&old, 2).anchor(&new), Some(3));
+	}
+
+	#[test]
+	fn anchor_ignores_case_and_punctuation() {
+		let old = chapter(&["\"Hello,\" she said."]);
+		let new = chapter(&["", "hello she SAID"]);
+		assert_eq!(bookmark(&old, 0).anchor(&new), Some(1));
+	}
+
+	#[test]
+	fn anchor_picks_the_nearest_of_repeated_paragraphs() {
+		let old = chapter(&["A", "Again.", "B", "C", "D", "Again."]);
+		let new = chapter(&["A", "Again.", "X", "B", "C", "D", "Again."]);
+		assert_eq!(bookmark(&old, 5).anchor(&new), Some(6));
+	}
+
+	#[test]
+	fn anchor_is_none_if_the_paragraph_is_gone() {
+		let old = chapter(&["One.", "Two."]);
+		let new = chapter(&["One.", "Something else."]);
+		assert_eq!(bookmark(&old, 1).anchor(&new), None);
+	}
+}
@@ -0,0 +1,385 @@
+use std::env;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Subcommand;
+use ln_lib::HttpConfig;
+use serde::{Deserialize, Serialize};
+use toml::Value;
+
+use crate::data_dir;
+use crate::export::ExportConfig;
+use crate::menu::PickerConfig;
+use crate::notify::NotifyConfig;
+use crate::reader::ReaderConfig;
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+	/// print the settings after the environment variables and flags were applied
+	Show,
+
+	/// print the path of the config file
+	Path,
+}
+
+/// The settings of ln-cli from `config.toml` in the config directory
+///
+/// Every setting can be overwritten with an environment variable like `LN_CLI_HTTP_TIMEOUT=10s`
+/// and then with a flag like `--set http.timeout=10s`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	/// The site the lightnovels are read from
+	pub source: String,
+	/// The directory the lightnovels are downloaded to, `store` in the data directory if not set
+	pub download_dir: Option<PathBuf>,
+	/// Read only from downloaded lightnovels
+	pub offline: bool,
+	pub http: HttpSettings,
+	pub cache: CacheConfig,
+	pub export: ExportConfig,
+	pub picker: PickerConfig,
+	pub reader: ReaderConfig,
+	pub notify: NotifyConfig,
+}
+
+/// The `[http]` settings of the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+	pub timeout: Option<Span>,
+	/// The most requests in a second
+	pub rate_limit: Option<f64>,
+	/// A proxy like `http://localhost:3128`, the `https_proxy` variable is used if not set
+	pub proxy: Option<String>,
+	pub user_agent: Option<String>,
+}
+
+/// The `[cache]` settings of the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+	pub enabled: bool,
+	/// The directory of the cached pages, `ln-cli` in the cache directory if not set
+	pub dir: Option<PathBuf>,
+	/// How long the lists of lightnovels and chapters are cached, they aren't cached with `0s`
+	pub index_ttl: Span,
+	/// How long the text of the chapters is cached
+	pub chapter_ttl: Span,
+}
+
+const SECTIONS: [&str; 6] = ["http", "cache", "export", "picker", "reader", "notify"];
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			source: HttpConfig::default().site,
+			download_dir: None,
+			offline: false,
+			http: HttpSettings::default(),
+			cache: CacheConfig::default(),
+			export: ExportConfig::default(),
+			picker: PickerConfig::default(),
+			reader: ReaderConfig::default(),
+			notify: NotifyConfig::default(),
+		}
+	}
+}
+
+impl Default for HttpSettings {
+	fn default() -> Self {
+		Self {
+			timeout: HttpConfig::default().timeout.map(Span),
+			rate_limit: None,
+			proxy: None,
+			user_agent: None,
+		}
+	}
+}
+
+impl Default for CacheConfig {
+	fn default() -> Self {
+		let http = HttpConfig::default();
+
+		Self {
+			enabled: true,
+			dir: None,
+			index_ttl: Span(http.index_ttl),
+			chapter_ttl: Span(http.chapter_ttl),
+		}
+	}
+}
+
+impl Config {
+	/// Reads the config file and puts the `LN_CLI_*` environment variables and then the `--set` flags on top
+	///
+	/// Every setting has a default so the file doesn't need to exist.
+	pub fn load(overrides: &[String]) -> Result<Self, Box<dyn Error>> {
+		let path = config_path()?;
+
+		let mut value = match fs::read_to_string(&path) {
+			Ok(content) => match content.parse::<Value>() {
+				Ok(value) => value,
+				Err(err) => return Err(format!("There is a problem in the config {}: {}", path.display(), err).into()),
+			},
+			Err(err) if err.kind() == ErrorKind::NotFound => Value::Table(Default::default()),
+			Err(err) => return Err(err.into()),
+		};
+
+		apply(&mut value, env::vars(), overrides)?;
+
+		let mut config: Self = match value.try_into() {
+			Ok(config) => config,
+			Err(err) => return Err(format!("There is a problem in the settings of {} or the environment: {}", path.display(), err).into()),
+		};
+
+		if config.download_dir.is_none() {
+			config.download_dir = Some(data_dir()?.join("store"));
+		}
+		if config.cache.dir.is_none() {
+			config.cache.dir = Some(dirs::cache_dir().ok_or("Could not find the cache directory")?.join("ln-cli"));
+		}
+
+		Ok(config)
+	}
+
+	/// The settings of the requests of ln-lib
+	pub fn get_http_config(&self) -> HttpConfig {
+		HttpConfig {
+			site: self.source.clone(),
+			timeout: self.http.timeout.map(|timeout| timeout.0),
+			user_agent: self.http.user_agent.clone(),
+			proxy: self.http.proxy.clone(),
+			rate_limit: self.http.rate_limit,
+			cache_dir: self.cache.dir.clone().filter(|_| self.cache.enabled),
+			index_ttl: self.cache.index_ttl.0,
+			chapter_ttl: self.cache.chapter_ttl.0,
+		}
+	}
+}
+
+/// Prints the effective settings or the path of the config
+pub fn print_config(command: ConfigCommand, config: &Config) -> Result<(), Box<dyn Error>> {
+	match command {
+		ConfigCommand::Show => print!("{}", toml::to_string(&shown(config)?)?),
+		ConfigCommand::Path => println!("{}", config_path()?.display()),
+	}
+
+	Ok(())
+}
+
+/// The settings to print, the password of the mail relay is hidden
+fn shown(config: &Config) -> Result<Value, Box<dyn Error>> {
+	let mut value = Value::try_from(config)?;
+
+	let password = value
+		.get_mut("notify")
+		.and_then(|notify| notify.get_mut("email"))
+		.and_then(|email| email.get_mut("password"));
+	if let Some(password) = password {
+		*password = Value::String("********".to_string());
+	}
+
+	Ok(value)
+}
+
+/// The directory of the config, `$XDG_CONFIG_HOME/ln-cli` on Linux
+pub fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+	Ok(dirs::config_dir().ok_or("Could not find the config directory")?.join("ln-cli"))
+}
+
+fn config_path() -> Result<PathBuf, Box<dyn Error>> {
+	Ok(config_dir()?.join("config.toml"))
+}
+
+/// Puts the `LN_CLI_*` variables of the environment and then the `--set` flags on top of the settings of the file
+fn apply(value: &mut Value, vars: impl IntoIterator<Item = (String, String)>, overrides: &[String]) -> Result<(), Box<dyn Error>> {
+	for (name, text) in vars {
+		if let Some(key) = name.strip_prefix("LN_CLI_") {
+			set(value, &env_key(key), &text);
+		}
+	}
+
+	for setting in overrides {
+		let (key, text) = setting
+			.split_once('=')
+			.ok_or_else(|| format!("{} is not a setting, use --set section.key=value", setting))?;
+		set(value, &key.trim().split('.').collect::<Vec<_>>(), text.trim());
+	}
+
+	Ok(())
+}
+
+/// The path of a setting in an environment variable, `HTTP_RATE_LIMIT` is `http.rate_limit`
+fn env_key(name: &str) -> Vec<String> {
+	let name = name.to_lowercase();
+
+	for section in SECTIONS {
+		if let Some(key) = name.strip_prefix(section).and_then(|key| key.strip_prefix('_')) {
+			return vec![section.to_string(), key.to_string()];
+		}
+	}
+
+	vec![name]
+}
+
+/// Sets the setting at the path, the text is read as a TOML value like `10`, `true` or `[1, 2]` or else as a string
+fn set<S: AsRef<str>>(value: &mut Value, path: &[S], text: &str) {
+	let (key, sections) = match path.split_last() {
+		Some(split) => split,
+		None => return,
+	};
+
+	let mut table = value;
+	for section in sections {
+		let entry = match table {
+			Value::Table(entries) => entries
+				.entry(section.as_ref().to_string())
+				.or_insert_with(|| Value::Table(Default::default())),
+			_ => return,
+		};
+		table = entry;
+	}
+
+	let parsed = format!("value = {}", text)
+		.parse::<Value>()
+		.ok()
+		.and_then(|parsed| parsed.get("value").cloned())
+		.unwrap_or_else(|| Value::String(text.to_string()));
+
+	if let Value::Table(entries) = table {
+		entries.insert(key.as_ref().to_string(), parsed);
+	}
+}
+
+/// A duration written like `30s`, `10m`, `12h` or `7d`, a plain number is in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SpanText", into = "String")]
+pub struct Span(pub Duration);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpanText {
+	Seconds(u64),
+	Text(String),
+}
+
+impl TryFrom<SpanText> for Span {
+	type Error = String;
+
+	fn try_from(value: SpanText) -> Result<Self, Self::Error> {
+		let text = match value {
+			SpanText::Seconds(secs) => return Ok(Self(Duration::from_secs(secs))),
+			SpanText::Text(text) => text,
+		};
+
+		let trimmed = text.trim();
+		let (number, unit) = trimmed.split_at(trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(trimmed.len()));
+		let scale = match unit.trim() {
+			"" | "s" => 1,
+			"m" => 60,
+			"h" => 60 * 60,
+			"d" => 24 * 60 * 60,
+			_ => return Err(format!("{} is not a duration, use something like 30s, 10m, 12h or 7d", text)),
+		};
+
+		match number.parse::<u64>().ok().and_then(|number| number.checked_mul(scale)) {
+			Some(secs) => Ok(Self(Duration::from_secs(secs))),
+			None => Err(format!("{} is not a duration, use something like 30s, 10m, 12h or 7d", text)),
+		}
+	}
+}
+
+impl Display for Span {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let secs = self.0.as_secs();
+
+		match [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60)]
+			.iter()
+			.find(|(_, scale)| secs > 0 && secs % scale == 0)
+		{
+			Some((unit, scale)) => write!(f, "{}{}", secs / scale, unit),
+			None => write!(f, "{}s", secs),
+		}
+	}
+}
+
+impl From<Span> for String {
+	fn from(span: Span) -> Self {
+		span.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::notify::EmailConfig;
+
+	fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+		vars.iter().map(|(name, text)| (name.to_string(), text.to_string())).collect()
+	}
+
+	#[test]
+	fn shown_hides_the_password() {
+		let mut config = Config::default();
+		config.notify.email = Some(EmailConfig {
+			password: Some("secret".to_string()),
+			..Default::default()
+		});
+
+		let shown = toml::to_string(&shown(&config).unwrap()).unwrap();
+		assert!(!shown.contains("secret"));
+		assert!(shown.contains("password = \"********\""));
+	}
+
+	#[test]
+	fn env_key_splits_off_the_section() {
+		assert_eq!(env_key("HTTP_RATE_LIMIT"), vec!["http", "rate_limit"]);
+		assert_eq!(env_key("CACHE_INDEX_TTL"), vec!["cache", "index_ttl"]);
+		assert_eq!(env_key("DOWNLOAD_DIR"), vec!["download_dir"]);
+	}
+
+	#[test]
+	fn set_reads_toml_values_and_else_strings() {
+		let mut value = Value::Table(Default::default());
+		set(&mut value, &["http", "rate_limit"], "2.5");
+		set(&mut value, &["offline"], "true");
+		set(&mut value, &["http", "proxy"], "http://localhost:3128");
+
+		assert_eq!(value["http"]["rate_limit"], Value::Float(2.5));
+		assert_eq!(value["offline"], Value::Boolean(true));
+		assert_eq!(value["http"]["proxy"], Value::String("http://localhost:3128".to_string()));
+	}
+
+	#[test]
+	fn flags_come_before_the_environment_and_the_environment_before_the_file() {
+		let file = "source = \"http://file\"\n[http]\ntimeout = \"5s\"\nrate_limit = 1.0\n";
+		let env = vars(&[("LN_CLI_HTTP_TIMEOUT", "10s"), ("LN_CLI_SOURCE", "http://env"), ("OTHER_TIMEOUT", "1s")]);
+
+		let mut value = file.parse::<Value>().unwrap();
+		apply(&mut value, env, &["http.timeout=20s".to_string()]).unwrap();
+		let config: Config = value.try_into().unwrap();
+
+		assert_eq!(config.http.timeout, Some(Span(Duration::from_secs(20))));
+		assert_eq!(config.source, "http://env");
+		assert_eq!(config.http.rate_limit, Some(1.0));
+		assert!(apply(&mut Value::Table(Default::default()), Vec::new(), &["http.timeout".to_string()]).is_err());
+	}
+
+	#[test]
+	fn span_reads_and_writes_units() {
+		let span = |text: &str| Span::try_from(SpanText::Text(text.to_string()));
+		assert_eq!(span("90"), Ok(Span(Duration::from_secs(90))));
+		assert_eq!(span("2h"), Ok(Span(Duration::from_secs(2 * 60 * 60))));
+		assert!(span("3w").is_err());
+		assert!(span("99999999999999999d").is_err());
+
+		assert_eq!(Span(Duration::from_secs(7 * 24 * 60 * 60)).to_string(), "7d");
+		assert_eq!(Span(Duration::from_secs(90)).to_string(), "90s");
+		assert_eq!(Span(Duration::ZERO).to_string(), "0s");
+	}
+}
@@ -0,0 +1,156 @@
+use std::error::Error;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use ln_lib::{Lightnovel, LightnovelChapter, Store};
+
+/// A list of chapter ranges like `1-50,75,100-`
+#[derive(Debug, Clone)]
+pub struct ChapterSelection(Vec<(usize, Option<usize>)>);
+
+impl ChapterSelection {
+	pub fn ranges(&self) -> Vec<RangeInclusive<usize>> {
+		self.0.iter().map(|(start, end)| *start..=end.unwrap_or(usize::MAX)).collect()
+	}
+
+	/// If a chapter is in more than one of the ranges
+	pub fn overlaps(&self) -> bool {
+		let ranges = self.ranges();
+		ranges
+			.iter()
+			.enumerate()
+			.any(|(i, a)| ranges[i + 1..].iter().any(|b| a.start() <= b.end() && b.start() <= a.end()))
+	}
+
+	pub fn contains(&self, chapter_number: usize) -> bool {
+		self
+			.0
+			.iter()
+			.any(|(start, end)| chapter_number >= *start && end.map(|end| chapter_number <= end).unwrap_or(true))
+	}
+}
+
+impl FromStr for ChapterSelection {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| format!("'{}' is not a chapter number", n.trim()));
+
+		let mut ranges = Vec::new();
+		for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+			let range = match part.split_once('-') {
+				Some((start, end)) => {
+					let start = if start.trim().is_empty() { 1 } else { parse(start)? };
+					let end = if end.trim().is_empty() { None } else { Some(parse(end)?) };
+					(start, end)
+				}
+				None => {
+					let number = parse(part)?;
+					(number, Some(number))
+				}
+			};
+
+			if range.1.map(|end| end < range.0).unwrap_or(false) {
+				return Err(format!("'{}' is not a valid chapter range", part));
+			}
+			ranges.push(range);
+		}
+
+		if ranges.is_empty() {
+			return Err("There is no chapter range".to_string());
+		}
+
+		Ok(Self(ranges))
+	}
+}
+
+/// Downloads the chapters into the store and skips the chapters that are already downloaded
+///
+/// Returns the chapters that failed to download.
+pub async fn download(ln: &Lightnovel, chapters: Vec<LightnovelChapter>, store: &Store) -> Result<Vec<LightnovelChapter>, Box<dyn Error>> {
+	store.save_lightnovel(ln, &chapters)?;
+
+	let total = chapters.len();
+	let missing = chapters
+		.into_iter()
+		.filter(|chapter| !store.has_chapter(chapter.get_url()))
+		.collect::<Vec<_>>();
+	let skipped = total - missing.len();
+
+	let bar = ProgressBar::new(missing.len() as u64);
+	bar.set_style(ProgressStyle::with_template(
+		"[{elapsed_precise}] {wide_bar} {pos}/{len} ETA {eta} {msg}",
+	)?);
+
+	let mut failed = Vec::new();
+	for mut chapter in missing {
+		bar.set_message(format!("{} failed | {}", failed.len(), chapter.get_title()));
+
+		let downloaded = match chapter.scrape().await {
+			Ok(true) => store.save_chapter(&chapter).is_ok(),
+			Ok(false) | Err(_) => false,
+		};
+		if !downloaded {
+			failed.push(chapter);
+		}
+
+		bar.inc(1);
+	}
+
+	bar.finish_with_message(format!("{} failed", failed.len()));
+
+	println!(
+		"{}: {} downloaded, {} already downloaded, {} failed",
+		ln.get_title(),
+		total - skipped - failed.len(),
+		skipped,
+		failed.len()
+	);
+	for chapter in &failed {
+		eprintln!("Failed to download: {}", chapter.get_title());
+	}
+
+	Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_numbers_and_ranges() {
+		let selection = "1-3, 7,10-".parse::<ChapterSelection>().unwrap();
+		assert_eq!(selection.ranges(), vec![1..=3, 7..=7, 10..=usize::MAX]);
+	}
+
+	#[test]
+	fn a_range_without_start_starts_at_the_first_chapter() {
+		let selection = "-5".parse::<ChapterSelection>().unwrap();
+		assert_eq!(selection.ranges(), vec![1..=5]);
+	}
+
+	#[test]
+	fn rejects_wrong_selections() {
+		assert!("".parse::<ChapterSelection>().is_err());
+		assert!(" , ".parse::<ChapterSelection>().is_err());
+		assert!("5-3".parse::<ChapterSelection>().is_err());
+		assert!("one".parse::<ChapterSelection>().is_err());
+		assert!("1-x".parse::<ChapterSelection>().is_err());
+	}
+
+	#[test]
+	fn overlapping_ranges() {
+		assert!(!"1-10,11-20,21-".parse::<ChapterSelection>().unwrap().overlaps());
+		assert!("1-10,10-20".parse::<ChapterSelection>().unwrap().overlaps());
+		assert!("50-,1-100".parse::<ChapterSelection>().unwrap().overlaps());
+		assert!("5,1-".parse::<ChapterSelection>().unwrap().overlaps());
+	}
+
+	#[test]
+	fn contains_the_chapters_of_every_range() {
+		let selection = "1-3,7,10-".parse::<ChapterSelection>().unwrap();
+		let contained = (0..=12).filter(|number| selection.contains(*number)).collect::<Vec<_>>();
+		assert_eq!(contained, vec![1, 2, 3, 7, 10, 11, 12]);
+	}
+}
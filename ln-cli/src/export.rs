@@ -0,0 +1,401 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{ArgEnum, Args};
+use indicatif::{ProgressBar, ProgressStyle};
+use ln_lib::export::calibre;
+use ln_lib::export::epub::{self, Contents, EpubOptions};
+use ln_lib::export::fb2::{self, Fb2Options};
+use ln_lib::export::html::{self, HtmlOptions};
+use ln_lib::export::json::{self, JsonFormat, NdjsonWriter};
+use ln_lib::export::pdf::{self, PageSize, PdfFont, PdfOptions};
+use ln_lib::export::text::{self, TextFormat, TextLayout, TextOptions};
+use ln_lib::export::{Cover, Volume, VolumeRule};
+use ln_lib::{Lightnovel, LightnovelChapter, Store};
+use serde::{Deserialize, Serialize};
+
+use crate::download::ChapterSelection;
+use crate::menu::PickerConfig;
+use crate::select::{interactive, NovelArgs};
+use crate::{Failure, DOWNLOAD_FAILED};
+
+#[derive(ArgEnum, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+	Epub,
+	Fb2,
+	Html,
+	Json,
+	Markdown,
+	Ndjson,
+	Pdf,
+	Text,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+	#[clap(flatten)]
+	novel: NovelArgs,
+
+	/// the format of the export [default: epub]
+	#[clap(short, long, arg_enum)]
+	format: Option<ExportFormat>,
+
+	/// the chapters to export like 1-50,75,100- (all chapters if not set)
+	#[clap(long)]
+	chapters: Option<ChapterSelection>,
+
+	/// the directory to write the export to [default: .]
+	#[clap(short, long)]
+	output: Option<PathBuf>,
+
+	/// an image to use as cover instead of the cover of the lightnovel
+	#[clap(long)]
+	cover: Option<PathBuf>,
+
+	/// a stylesheet to use instead of the default one (epub and html)
+	#[clap(long)]
+	css: Option<PathBuf>,
+
+	/// split the export into volumes with this many chapters
+	#[clap(long, conflicts_with = "volumes")]
+	volume_size: Option<usize>,
+
+	/// split the export into volumes by chapter ranges like 1-100,101-200, the other chapters make the last volume
+	#[clap(long)]
+	volumes: Option<ChapterSelection>,
+
+	/// write all chapters into one file instead of one file per chapter (markdown and text)
+	#[clap(long)]
+	single_file: bool,
+
+	/// the page size of the pdf: a4, a5, letter or 6in [default: a4]
+	#[clap(long)]
+	page_size: Option<PageSize>,
+
+	/// the margin of the pdf pages in millimetres [default: 20]
+	#[clap(long)]
+	margin: Option<f32>,
+
+	/// the font of the pdf: times, helvetica or courier [default: times]
+	#[clap(long)]
+	font: Option<PdfFont>,
+
+	/// the font size of the pdf in points [default: 11]
+	#[clap(long)]
+	font_size: Option<f32>,
+
+	/// add the new chapters to epubs which already exist instead of writing them again
+	#[clap(long)]
+	update: bool,
+
+	/// lay the export out as a Calibre library with metadata.opf and cover.jpg in the output directory
+	#[clap(long)]
+	calibre: bool,
+
+	/// the file names like "{novel}/{number:04} - {title}.{ext}" (markdown and text)
+	#[clap(long)]
+	template: Option<String>,
+}
+
+/// The `[export]` settings of the config, the flags of the export command are used instead when they are set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+	pub format: ExportFormat,
+	pub output: PathBuf,
+	pub css: Option<PathBuf>,
+	pub template: Option<String>,
+	pub page_size: PageSize,
+	/// The margin of the pdf pages in millimetres
+	pub margin: f32,
+	pub font: PdfFont,
+	/// The font size of the pdf in points
+	pub font_size: f32,
+}
+
+impl Default for ExportConfig {
+	fn default() -> Self {
+		Self {
+			format: ExportFormat::Epub,
+			output: PathBuf::from("."),
+			css: None,
+			template: None,
+			page_size: PageSize::default(),
+			margin: 20.0,
+			font: PdfFont::default(),
+			font_size: 11.0,
+		}
+	}
+}
+
+pub async fn export(args: ExportArgs, config: &ExportConfig, picker: &PickerConfig, store: &Store) -> Result<(), Box<dyn Error>> {
+	let format = args.format.unwrap_or(config.format);
+	let output = args.output.unwrap_or_else(|| config.output.clone());
+
+	let per_chapter = matches!(format, ExportFormat::Markdown | ExportFormat::Text) && !args.single_file;
+	if args.calibre && per_chapter {
+		return Err("A Calibre library needs one file per book, use --single-file".into());
+	}
+	let template = args.template.or_else(|| config.template.clone());
+	if let Some(template) = template
+		.as_ref()
+		.filter(|template| per_chapter && !template.contains("{number") && !template.contains("{title"))
+	{
+		return Err(
+			format!(
+				"The template {} gives every chapter the same file, use {{number}} or {{title}} in it",
+				template
+			)
+			.into(),
+		);
+	}
+
+	let volumes = match (args.volume_size, args.volumes) {
+		(Some(size), _) => VolumeRule::ChapterCount(size),
+		(None, Some(selection)) if selection.overlaps() => return Err("The ranges of --volumes overlap, a chapter can only be in one volume".into()),
+		(None, Some(selection)) => VolumeRule::ChapterRanges(selection.ranges()),
+		(None, None) => VolumeRule::Single,
+	};
+
+	let mut ln = match args.novel.find(interactive(None).then_some(picker)).await? {
+		Some(ln) => ln,
+		None => return Ok(()),
+	};
+
+	let chapters = ln
+		.scrape_index()
+		.await?
+		.into_iter()
+		.filter(|chapter| args.chapters.as_ref().map(|s| s.contains(chapter.get_chapter_number())).unwrap_or(true))
+		.collect::<Vec<_>>();
+
+	// The epubs of a Calibre library are written into their book folders, so they are updated there too
+	let epub_path = |volume: &Volume| {
+		if args.calibre {
+			calibre::book_dir(&output, &ln, volume).join(calibre::file_name(&ln, volume, "epub"))
+		} else {
+			output.join(epub::file_name(volume))
+		}
+	};
+
+	// NDJSON is written while the chapters are fetched, the other formats need all of them at once
+	let chapters = match format {
+		ExportFormat::Ndjson => chapters,
+		ExportFormat::Epub if args.update => {
+			// The chapters the epubs already hold aren't fetched again, they are only kept to split the volumes the same way
+			let exported = exported_chapters(&ln, &chapters, &volumes, epub_path)?;
+			let (mut kept, missing): (Vec<_>, Vec<_>) = chapters
+				.into_iter()
+				.partition(|chapter| exported.iter().any(|contents| contents.contains(chapter)));
+			kept.extend(fetch_chapters(missing, store).await?);
+			kept.sort_by_key(|chapter| chapter.get_chapter_number());
+			kept
+		}
+		_ => fetch_chapters(chapters, store).await?,
+	};
+
+	let cover = match (args.cover, &ln.get_details().cover) {
+		(Some(path), _) => Some(Cover::from_path(&path)?),
+		(None, Some(url)) if !Store::is_offline() => match Cover::fetch(url).await {
+			Ok(cover) => Some(cover),
+			Err(err) => {
+				eprintln!("The export has no cover, it could not be downloaded: {}", err);
+				None
+			}
+		},
+		(None, _) => None,
+	};
+
+	let css = match args.css.or_else(|| config.css.clone()) {
+		Some(path) => Some(fs::read_to_string(path)?),
+		None => None,
+	};
+
+	// Formats which aren't split into volumes make one book of the whole lightnovel
+	let books = match format {
+		ExportFormat::Epub | ExportFormat::Fb2 | ExportFormat::Html | ExportFormat::Pdf => volumes.clone(),
+		_ => VolumeRule::Single,
+	};
+	let book_cover = cover.clone();
+
+	let paths = match format {
+		ExportFormat::Epub => {
+			let options = EpubOptions {
+				css,
+				cover,
+				volumes,
+				update: args.update,
+				..Default::default()
+			};
+			epub::export_to(&ln, &chapters, &options, epub_path)?
+		}
+		ExportFormat::Fb2 => {
+			let options = Fb2Options {
+				cover,
+				volumes,
+				..Default::default()
+			};
+			fb2::export(&ln, &chapters, &options, &output)?
+		}
+		ExportFormat::Html => {
+			let options = HtmlOptions {
+				css,
+				cover,
+				volumes,
+				..Default::default()
+			};
+			html::export(&ln, &chapters, &options, &output)?
+		}
+		ExportFormat::Json => json::export(&ln, &chapters, JsonFormat::Json, &output)?,
+		ExportFormat::Ndjson => stream_ndjson(&ln, &chapters, store, &output).await?,
+		ExportFormat::Pdf => {
+			let options = PdfOptions {
+				page_size: args.page_size.unwrap_or(config.page_size),
+				margin: args.margin.unwrap_or(config.margin),
+				font: args.font.unwrap_or(config.font),
+				font_size: args.font_size.unwrap_or(config.font_size),
+				cover,
+				volumes,
+			};
+			pdf::export(&ln, &chapters, &options, &output)?
+		}
+		ExportFormat::Markdown | ExportFormat::Text => {
+			let options = TextOptions {
+				format: match format {
+					ExportFormat::Markdown => TextFormat::Markdown,
+					_ => TextFormat::Plain,
+				},
+				layout: if args.single_file {
+					TextLayout::SingleFile
+				} else {
+					TextLayout::FilePerChapter
+				},
+				template,
+			};
+			text::export(&ln, &chapters, &options, &output)?
+		}
+	};
+
+	let paths = if args.calibre {
+		calibre_library(&ln, &chapters, &books, book_cover.as_ref(), &output, paths)?
+	} else {
+		paths
+	};
+
+	for path in paths {
+		println!("{}", path.display());
+	}
+
+	Ok(())
+}
+
+/// Reads which chapters the epubs of the volumes hold, it fails if there is no epub to update
+fn exported_chapters<F>(ln: &Lightnovel, chapters: &[LightnovelChapter], volumes: &VolumeRule, path_of: F) -> Result<Vec<Contents>, Box<dyn Error>>
+where
+	F: Fn(&Volume) -> PathBuf,
+{
+	let paths = volumes.split(ln, chapters).iter().map(path_of).collect::<Vec<_>>();
+	let exported = paths
+		.iter()
+		.filter(|path| path.exists())
+		.map(|path| epub::contents(path))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	match paths.first() {
+		Some(path) if exported.is_empty() => Err(format!("There is no epub to update at {}, export it without --update first", path.display()).into()),
+		_ => Ok(exported),
+	}
+}
+
+/// Moves the exported files into the book folders of a Calibre library at the root, one file per volume
+fn calibre_library(
+	ln: &Lightnovel, chapters: &[LightnovelChapter], books: &VolumeRule, cover: Option<&Cover>, root: &Path, paths: Vec<PathBuf>,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	let volumes = books.split(ln, chapters);
+	if volumes.len() != paths.len() {
+		return Err(format!("{} files were exported for {} books", paths.len(), volumes.len()).into());
+	}
+
+	let mut moved = Vec::with_capacity(paths.len());
+	for (volume, path) in volumes.iter().zip(paths) {
+		let dir = calibre::write_book(root, ln, volume, cover)?;
+		let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+		let target = dir.join(calibre::file_name(ln, volume, extension));
+		if path != target {
+			fs::rename(&path, &target)?;
+		}
+		moved.push(target);
+	}
+
+	Ok(moved)
+}
+
+/// Writes the NDJSON line of every chapter as soon as it is fetched and returns the path of the file
+async fn stream_ndjson(ln: &Lightnovel, chapters: &[LightnovelChapter], store: &Store, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+	if chapters.is_empty() {
+		return Err("There are no chapters to export".into());
+	}
+
+	let (path, file) = json::create(ln, JsonFormat::Ndjson, dir)?;
+	let mut writer = NdjsonWriter::new(ln, chapters.len(), file)?;
+	fetch_each(chapters.to_vec(), store, |chapter| Ok(writer.write_chapter(&chapter)?)).await?;
+	writer.finish()?;
+
+	Ok(vec![path])
+}
+
+/// Gets the content of the chapters from the store or scrapes them if they aren't downloaded
+pub async fn fetch_chapters(chapters: Vec<LightnovelChapter>, store: &Store) -> Result<Vec<LightnovelChapter>, Box<dyn Error>> {
+	let mut fetched = Vec::with_capacity(chapters.len());
+	fetch_each(chapters, store, |chapter| {
+		fetched.push(chapter);
+		Ok(())
+	})
+	.await?;
+
+	Ok(fetched)
+}
+
+/// Hands every chapter with content to `each` once it was loaded, the chapters without content are skipped
+///
+/// A chapter which can't be scraped doesn't stop the others, but it fails once all chapters were tried
+/// so an export never leaves chapters out without telling.
+async fn fetch_each<F>(chapters: Vec<LightnovelChapter>, store: &Store, mut each: F) -> Result<(), Box<dyn Error>>
+where
+	F: FnMut(LightnovelChapter) -> Result<(), Box<dyn Error>>,
+{
+	let bar = ProgressBar::new(chapters.len() as u64);
+	bar.set_style(ProgressStyle::with_template(
+		"[{elapsed_precise}] {wide_bar} {pos}/{len} ETA {eta} {msg}",
+	)?);
+
+	let mut failed = 0;
+	for mut chapter in chapters {
+		bar.set_message(chapter.get_title().clone());
+
+		let has_content = match store.load_chapter(&mut chapter) {
+			Ok(true) => Ok(true),
+			_ => chapter.scrape().await,
+		};
+
+		match has_content {
+			Ok(true) => each(chapter)?,
+			Ok(false) => bar.println(format!("Skipped the chapter without content: {}", chapter.get_title())),
+			Err(err) => {
+				bar.println(format!("Failed to load {}: {}", chapter.get_title(), err));
+				failed += 1;
+			}
+		}
+
+		bar.inc(1);
+	}
+
+	bar.finish_and_clear();
+
+	if failed > 0 {
+		return Err(Failure::new(DOWNLOAD_FAILED, format!("{} chapters failed to load", failed)).into());
+	}
+	Ok(())
+}
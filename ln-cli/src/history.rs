@@ -0,0 +1,44 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::{data_dir, write_file};
+
+/// The chapters which were read to their end, they are marked as read in the chapter lists
+#[derive(Debug, Default)]
+pub struct History {
+	path: PathBuf,
+	read: BTreeSet<String>,
+}
+
+impl History {
+	pub fn load() -> Result<Self, Box<dyn Error>> {
+		let path = data_dir()?.join("history.json");
+
+		let read = match fs::read_to_string(&path) {
+			Ok(content) => match serde_json::from_str(&content) {
+				Ok(read) => read,
+				Err(err) => return Err(format!("There is a problem in the history {}: {}", path.display(), err).into()),
+			},
+			Err(err) if err.kind() == ErrorKind::NotFound => BTreeSet::new(),
+			Err(err) => return Err(format!("Could not read the history {}: {}", path.display(), err).into()),
+		};
+
+		Ok(Self { path, read })
+	}
+
+	pub fn save(&self) -> Result<(), Box<dyn Error>> {
+		write_file(&self.path, &serde_json::to_string_pretty(&self.read)?)
+	}
+
+	pub fn is_read(&self, chapter_url: &str) -> bool {
+		self.read.contains(chapter_url)
+	}
+
+	/// Marks the chapter as read, returns if it wasn't read before
+	pub fn mark_read(&mut self, chapter_url: &str) -> bool {
+		self.read.insert(chapter_url.to_string())
+	}
+}
@@ -0,0 +1,203 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use clap::{Args, Subcommand};
+use ln_lib::{Lightnovel, LightnovelChapter, Store};
+use tokio::sync::Semaphore;
+
+use crate::download::download;
+use crate::notify::{notify, NotifyConfig};
+use crate::output::{print_library, print_updates, OutputFormat};
+use crate::{Failure, DOWNLOAD_FAILED, NEW_CHAPTERS};
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+	/// print the directory of the downloaded files
+	Path,
+
+	/// print how much space the downloaded files take
+	Size,
+
+	/// delete the downloaded files of a lightnovel or of every lightnovel
+	Clear {
+		/// the title of the downloaded lightnovel to delete
+		novel: Option<String>,
+	},
+}
+
+#[derive(Args)]
+pub struct UpdateArgs {
+	/// download the new chapters
+	#[clap(long)]
+	download: bool,
+
+	/// how many lightnovels are checked at the same time, the requests still keep to http.rate_limit
+	#[clap(long, default_value = "4")]
+	jobs: usize,
+
+	/// don't tell the notifiers of the config about the new chapters
+	#[clap(long)]
+	no_notify: bool,
+
+	/// how to print the new chapters
+	#[clap(long, arg_enum)]
+	format: Option<OutputFormat>,
+}
+
+/// Prints the downloaded lightnovels and how many of their chapters are downloaded
+pub fn library(store: &Store, format: Option<OutputFormat>) -> Result<(), Box<dyn Error>> {
+	let mut entries = Vec::new();
+	for ln in store.lightnovels()? {
+		let chapters = store.chapters(ln.get_url())?;
+		let downloaded = chapters.iter().filter(|chapter| store.has_chapter(chapter.get_url())).count();
+
+		entries.push((ln, downloaded, chapters.len()));
+	}
+
+	print_library(&entries, format.unwrap_or(OutputFormat::Plain))?;
+	Ok(())
+}
+
+/// Compares the chapters of every downloaded lightnovel with the site and stores the new chapters in the index
+///
+/// The lightnovels are checked at the same time and the notifiers of the config are told about the new chapters.
+/// `--download` also gets the new chapters an earlier update found without downloading them. It fails with its own
+/// exit code if there are new chapters, so a cron job can act on it.
+pub async fn update(store: &Store, args: UpdateArgs, config: &NotifyConfig) -> Result<(), Box<dyn Error>> {
+	let jobs = Arc::new(Semaphore::new(args.jobs.max(1)));
+
+	let checks = store
+		.lightnovels()?
+		.into_iter()
+		.map(|ln| {
+			let jobs = jobs.clone();
+			let store = store.clone();
+			let title = ln.get_title().clone();
+
+			(
+				title,
+				tokio::spawn(async move {
+					let _job = jobs.acquire_owned().await.map_err(|err| err.to_string())?;
+					check(ln, &store).await
+				}),
+			)
+		})
+		.collect::<Vec<_>>();
+
+	let mut updates = Vec::new();
+	let mut missing = Vec::new();
+	let mut failed = 0;
+	for (title, check) in checks {
+		match check.await? {
+			Ok((ln, new, not_downloaded)) => {
+				missing.push(not_downloaded);
+				updates.push((ln, new));
+			}
+			Err(err) => {
+				eprintln!("{}: could not look for new chapters: {}", title, err);
+				failed += 1;
+			}
+		}
+	}
+
+	print_updates(&updates, args.format.unwrap_or(OutputFormat::Plain))?;
+
+	if !args.no_notify {
+		for failure in notify(config, &updates).await {
+			eprintln!("{}", failure);
+		}
+	}
+
+	if args.download {
+		let mut failed_downloads = 0;
+		for ((ln, _), chapters) in updates.iter().zip(missing).filter(|(_, chapters)| !chapters.is_empty()) {
+			let count = chapters.len();
+			match download(ln, chapters, store).await {
+				Ok(failed) => failed_downloads += failed.len(),
+				Err(err) => {
+					eprintln!("{}: could not download the new chapters: {}", ln.get_title(), err);
+					failed_downloads += count;
+				}
+			}
+		}
+
+		if failed_downloads > 0 {
+			return Err(Failure::new(DOWNLOAD_FAILED, format!("{} chapters failed to download", failed_downloads)).into());
+		}
+	}
+
+	let updated = updates.iter().any(|(_, new)| !new.is_empty());
+	match (updated, failed) {
+		(true, 0) => Err(Failure::silent(NEW_CHAPTERS).into()),
+		(true, _) => Err(Failure::new(NEW_CHAPTERS, format!("{} lightnovels could not be checked for new chapters", failed)).into()),
+		(false, 0) => Ok(()),
+		(false, _) => Err(format!("{} lightnovels could not be checked for new chapters", failed).into()),
+	}
+}
+
+/// Scrapes the whole chapter index of the lightnovel and adds the new chapters to the stored index
+///
+/// The new chapters are the ones after the last chapter of the stored index, the older chapters which were never
+/// downloaded aren't new. It returns the lightnovel, the new chapters and the chapters to download: the new ones
+/// and the ones of the stored index which aren't downloaded like the new chapters of earlier updates.
+async fn check(mut ln: Lightnovel, store: &Store) -> Result<(Lightnovel, Vec<LightnovelChapter>, Vec<LightnovelChapter>), String> {
+	let known = store.chapters(ln.get_url()).map_err(|err| err.to_string())?;
+	let index = ln.scrape_index().await.map_err(|err| err.to_string())?;
+
+	let last = index
+		.iter()
+		.rposition(|chapter| known.iter().any(|known| known.get_url() == chapter.get_url()));
+	let new = index.into_iter().skip(last.map_or(0, |last| last + 1)).collect::<Vec<_>>();
+
+	if !new.is_empty() {
+		store.save_lightnovel(&ln, &new).map_err(|err| err.to_string())?;
+	}
+
+	let not_downloaded = known
+		.into_iter()
+		.filter(|chapter| !store.has_chapter(chapter.get_url()))
+		.chain(new.iter().cloned())
+		.collect();
+
+	Ok((ln, new, not_downloaded))
+}
+
+pub fn cache(command: CacheCommand, store: &Store) -> Result<(), Box<dyn Error>> {
+	match command {
+		CacheCommand::Path => println!("{}", store.root().display()),
+		CacheCommand::Size => println!("{}", human_size(store.size())),
+		CacheCommand::Clear { novel: Some(novel) } => {
+			let ln = store
+				.lightnovels()?
+				.into_iter()
+				.find(|ln| ln.get_title().to_lowercase().contains(&novel.to_lowercase()))
+				.ok_or_else(|| format!("There is no downloaded lightnovel like {}", novel))?;
+
+			let removed = store.remove_lightnovel(ln.get_url())?;
+			println!("{}: {} chapters deleted", ln.get_title(), removed);
+		}
+		CacheCommand::Clear { novel: None } => {
+			store.clear()?;
+			println!("Deleted every downloaded lightnovel");
+		}
+	}
+
+	Ok(())
+}
+
+fn human_size(bytes: u64) -> String {
+	let units = ["B", "KiB", "MiB", "GiB"];
+
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < units.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	if unit == 0 {
+		format!("{} {}", bytes, units[0])
+	} else {
+		format!("{:.1} {}", size, units[unit])
+	}
+}
@@ -0,0 +1,103 @@
+use std::error::Error;
+
+use clap::{ArgGroup, Args};
+use ln_lib::{LightnovelCategory, LightnovelList};
+
+use crate::download::ChapterSelection;
+use crate::menu::PickerConfig;
+use crate::output::{print_chapters, print_novels, print_text, OutputFormat};
+use crate::select::{find_chapter, interactive, NovelArgs};
+use crate::{Failure, NOT_FOUND};
+
+#[derive(Args)]
+#[clap(group(ArgGroup::new("category").required(true).args(&["genre", "completed", "latest"])))]
+pub struct BrowseArgs {
+	/// list the lightnovels with the genre
+	#[clap(short)]
+	genre: Option<String>,
+
+	/// list the completed lightnovels
+	#[clap(short)]
+	completed: bool,
+
+	/// list the latest lightnovels
+	#[clap(short)]
+	latest: bool,
+
+	/// how many pages of lightnovels to list
+	#[clap(long, default_value = "1")]
+	pages: usize,
+
+	/// how to print the lightnovels
+	#[clap(long, arg_enum)]
+	format: Option<OutputFormat>,
+}
+
+pub async fn search(query: String, pages: usize, format: Option<OutputFormat>) -> Result<(), Box<dyn Error>> {
+	print_list(LightnovelCategory::Title(query), pages, format).await
+}
+
+pub async fn browse(args: BrowseArgs) -> Result<(), Box<dyn Error>> {
+	let category = if let Some(genre) = args.genre {
+		LightnovelCategory::Genre(genre)
+	} else if args.completed {
+		LightnovelCategory::Completed
+	} else {
+		LightnovelCategory::Latest
+	};
+
+	print_list(category, args.pages, args.format).await
+}
+
+/// Prints the chapter index of the lightnovel
+pub async fn chapters(
+	novel: NovelArgs, selection: Option<ChapterSelection>, format: Option<OutputFormat>, picker: &PickerConfig,
+) -> Result<(), Box<dyn Error>> {
+	let mut ln = match novel.find(interactive(format).then_some(picker)).await? {
+		Some(ln) => ln,
+		None => return Ok(()),
+	};
+
+	let chapters = ln
+		.scrape_index()
+		.await?
+		.into_iter()
+		.filter(|chapter| selection.as_ref().map(|s| s.contains(chapter.get_chapter_number())).unwrap_or(true))
+		.collect::<Vec<_>>();
+
+	print_chapters(&chapters, format.unwrap_or(OutputFormat::Plain))?;
+	Ok(())
+}
+
+/// Prints the paragraphs of a chapter of the lightnovel
+pub async fn text(novel: NovelArgs, chapter: String, format: Option<OutputFormat>, picker: &PickerConfig) -> Result<(), Box<dyn Error>> {
+	let mut ln = match novel.find(interactive(format).then_some(picker)).await? {
+		Some(ln) => ln,
+		None => return Ok(()),
+	};
+
+	let mut chapter = find_chapter(ln.scrape_index().await?, &chapter)?;
+	if !chapter.scrape().await? {
+		return Err(Failure::new(NOT_FOUND, format!("{} has no text", chapter.get_title())).into());
+	}
+
+	print_text(&chapter, format.unwrap_or(OutputFormat::Plain))?;
+	Ok(())
+}
+
+/// Prints the lightnovels on the first pages of the category
+async fn print_list(category: LightnovelCategory, pages: usize, format: Option<OutputFormat>) -> Result<(), Box<dyn Error>> {
+	let mut list = LightnovelList::new(category)?;
+	list.scrape().await?;
+
+	let mut novels = list.to_vec();
+	for _ in 1..pages.max(1) {
+		if !list.next_scrape().await? {
+			break;
+		}
+		novels.extend(list.iter().cloned());
+	}
+
+	print_novels(&novels, format.unwrap_or(OutputFormat::Plain))?;
+	Ok(())
+}
@@ -1,22 +1,74 @@
+mod bookmark;
+mod config;
+mod download;
+mod export;
+mod history;
+mod library;
+mod list;
 mod menu;
+mod notify;
+mod output;
+mod reader;
+mod select;
 
-use menu::{show_chapters, show_ln};
+use bookmark::Bookmarks;
+use config::{print_config, Config, ConfigCommand};
+use download::{download, ChapterSelection};
+use export::{export, ExportArgs};
+use history::History;
+use library::{cache, library, update, CacheCommand, UpdateArgs};
+use list::{browse, chapters, search, text, BrowseArgs};
+use menu::{show_bookmarks, show_chapters, show_ln};
+use output::OutputFormat;
+use reader::{read, tui};
+use select::{interactive, NovelArgs};
 
 use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process;
 
-use std::cmp::{max, min};
-use tuikit::attr::*;
-use tuikit::event::{Event, Key};
-use tuikit::term::{Term, TermHeight};
-
-use clap::{AppSettings, ArgGroup, Parser};
-use ln_lib::{LightnovelCategory, LightnovelList};
+use clap::{AppSettings, ArgGroup, Args as ClapArgs, Parser, Subcommand};
+use ln_lib::{Lightnovel, LightnovelCategory, LightnovelChapter, LightnovelList, Store};
 
 #[derive(Parser)]
 #[clap(author, version, about)]
 #[clap(global_setting(AppSettings::DeriveDisplayOrder))]
-#[clap(group(ArgGroup::new("type").required(true).args(&["name", "genre", "completed", "latest"])))]
+#[clap(subcommand_negates_reqs = true)]
+#[clap(args_conflicts_with_subcommands = true)]
+#[clap(after_help = "EXIT CODES:
+    0    success
+    1    error
+    2    wrong arguments
+    3    no lightnovel or chapter was found
+    4    several lightnovels or chapters match, pick one with --index or the chapter number
+    5    some chapters failed to download
+    6    update found new chapters")]
 struct Args {
+	#[clap(flatten)]
+	read: ReadArgs,
+
+	/// read only from downloaded lightnovels
+	#[clap(long, global = true)]
+	offline: bool,
+
+	/// the directory of the downloaded lightnovels
+	#[clap(long, global = true)]
+	store: Option<PathBuf>,
+
+	/// change a setting of the config like http.timeout=10s, it can be used several times
+	#[clap(long, global = true, multiple_occurrences = true, value_name = "KEY=VALUE")]
+	set: Vec<String>,
+
+	#[clap(subcommand)]
+	command: Option<Command>,
+}
+
+#[derive(ClapArgs)]
+#[clap(group(ArgGroup::new("type").required(true).args(&["name", "genre", "completed", "latest"])))]
+struct ReadArgs {
 	/// search for the lightnovel you want to read
 	name: Option<String>,
 
@@ -37,80 +89,299 @@ struct Args {
 	stage: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-	let args = Args::parse();
+impl ReadArgs {
+	fn category(self) -> LightnovelCategory {
+		if let Some(name) = self.name {
+			LightnovelCategory::Title(name)
+		} else if let Some(genre) = self.genre {
+			LightnovelCategory::Genre(genre)
+		} else if self.completed {
+			LightnovelCategory::Completed
+		} else if self.latest {
+			LightnovelCategory::Latest
+		} else {
+			LightnovelCategory::Latest
+		}
+	}
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// list the lightnovels with a title like the search
+	Search {
+		/// the title to search for
+		query: String,
+
+		/// how many pages of results to list
+		#[clap(long, default_value = "1")]
+		pages: usize,
+
+		/// how to print the lightnovels
+		#[clap(long, arg_enum)]
+		format: Option<OutputFormat>,
+	},
+
+	/// list the lightnovels of a genre, the completed or the latest lightnovels
+	Browse(BrowseArgs),
+
+	/// list the chapters of a lightnovel
+	Chapters {
+		#[clap(flatten)]
+		novel: NovelArgs,
+
+		/// the chapters to list like 1-50,75,100- (all chapters if not set)
+		#[clap(long)]
+		chapters: Option<ChapterSelection>,
+
+		/// how to print the chapters
+		#[clap(long, arg_enum)]
+		format: Option<OutputFormat>,
+	},
 
-	let category = if let Some(name) = args.name {
-		LightnovelCategory::Title(name)
-	} else if let Some(genre) = args.genre {
-		LightnovelCategory::Genre(genre)
-	} else if args.completed {
-		LightnovelCategory::Completed
-	} else if args.latest {
-		LightnovelCategory::Latest
-	} else {
-		LightnovelCategory::Latest
+	/// print the text of a chapter
+	Text {
+		#[clap(flatten)]
+		novel: NovelArgs,
+
+		/// the number, url or title of the chapter
+		chapter: String,
+
+		/// how to print the text
+		#[clap(long, arg_enum)]
+		format: Option<OutputFormat>,
+	},
+
+	/// pick a lightnovel and a chapter and read it, this is the default
+	Read(ReadArgs),
+
+	/// list your bookmarks and highlights and jump to one
+	Bookmarks,
+
+	/// browse the library and the search results, their chapters and read them on one screen
+	Tui,
+
+	/// download the chapters of a lightnovel to read them offline
+	Download {
+		#[clap(flatten)]
+		novel: NovelArgs,
+
+		/// the chapters to download like 1-50,75,100- (select them in a menu if not set)
+		#[clap(long)]
+		chapters: Option<ChapterSelection>,
+	},
+
+	/// export the chapters of a lightnovel into an ebook
+	Export(ExportArgs),
+
+	/// list the downloaded lightnovels
+	Library {
+		/// how to print the lightnovels
+		#[clap(long, arg_enum)]
+		format: Option<OutputFormat>,
+	},
+
+	/// look for new chapters of the downloaded lightnovels
+	Update(UpdateArgs),
+
+	/// show or delete the downloaded files
+	Cache {
+		#[clap(subcommand)]
+		command: CacheCommand,
+	},
+
+	/// show the settings from config.toml, the environment and the flags
+	Config {
+		#[clap(subcommand)]
+		command: ConfigCommand,
+	},
+}
+
+/// A failure which ends ln-cli with its own exit code
+#[derive(Debug)]
+pub struct Failure {
+	code: i32,
+	message: String,
+}
+
+pub const NOT_FOUND: i32 = 3;
+pub const AMBIGUOUS: i32 = 4;
+pub const DOWNLOAD_FAILED: i32 = 5;
+pub const NEW_CHAPTERS: i32 = 6;
+
+impl Failure {
+	pub fn new(code: i32, message: String) -> Self {
+		Self { code, message }
+	}
+
+	/// A failure which only ends ln-cli with the exit code, the output already told what happened
+	pub fn silent(code: i32) -> Self {
+		Self::new(code, String::new())
+	}
+}
+
+impl fmt::Display for Failure {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.message)
+	}
+}
+
+impl Error for Failure {}
+
+#[tokio::main]
+async fn main() {
+	let err = match run().await {
+		Ok(()) => return,
+		Err(err) => err,
 	};
 
-	let mut list = LightnovelList::new(category)?;
-	list.scrape().await?;
+	if let Some(failure) = err.downcast_ref::<Failure>() {
+		if !failure.message.is_empty() {
+			eprintln!("Error: {}", failure);
+		}
+		process::exit(failure.code);
+	}
 
-	let mut ln = show_ln(&mut list).unwrap();
+	// The reader of the output went away like `ln-cli search x | head`
+	if err
+		.downcast_ref::<io::Error>()
+		.map(|err| err.kind() == ErrorKind::BrokenPipe)
+		.unwrap_or(false)
+	{
+		return;
+	}
 
-	ln.scrape().await?;
+	eprintln!("Error: {:?}", err);
+	process::exit(1);
+}
 
-	let mut chapter = show_chapters(&mut ln).await?.unwrap();
-	chapter.scrape().await?;
+async fn run() -> Result<(), Box<dyn Error>> {
+	let args = Args::parse();
+
+	let mut config = Config::load(&args.set)?;
+	if let Some(store) = args.store {
+		config.download_dir = Some(store);
+	}
+	config.offline |= args.offline;
 
-	let term: Term<()> = Term::with_height(TermHeight::Percent(100)).unwrap();
-	let mut line = 1;
-	let mut lineselect: i32 = 0;
-	let col = 0;
+	let store = Store::new(config.download_dir.clone().unwrap_or_default());
+	if config.offline {
+		store.clone().go_offline();
+	}
+	config.get_http_config().apply();
+
+	match args.command {
+		Some(Command::Bookmarks) => {
+			let mut bookmarks = Bookmarks::load()?;
+			let mut history = History::load()?;
 
-	let _ = term.present();
+			let bookmark = match show_bookmarks(&bookmarks) {
+				Some(bookmark) => bookmark,
+				None => return Ok(()),
+			};
 
-	while let Ok(ev) = term.poll_event() {
-		let _ = term.clear();
+			let ln = Lightnovel::new(bookmark.novel_title.clone(), bookmark.novel_url.clone());
+			let mut chapter = LightnovelChapter::new(bookmark.chapter_title.clone(), bookmark.chapter_url.clone(), bookmark.chapter_number);
+			chapter.scrape().await?;
 
-		let (_width, height) = term.term_size().unwrap();
+			let start = bookmark.anchor(&chapter).unwrap_or(bookmark.paragraph);
 
-		match ev {
-			Event::Key(Key::ESC) | Event::Key(Key::Char('q')) => break,
-			Event::Key(Key::Up) => lineselect = max(lineselect - 1, -1),
-			Event::Key(Key::Down) => lineselect = min(lineselect + 1, height as i32 + 1),
-			_ => {}
+			return read(&ln, chapter, start, &mut bookmarks, &mut history, &config.reader).await;
 		}
+		Some(Command::Tui) => return tui(&store, &mut Bookmarks::load()?, &mut History::load()?, &config.reader).await,
+		Some(Command::Download { novel, chapters }) => {
+			if config.offline {
+				return Err("Lightnovels can't be downloaded in offline mode".into());
+			}
 
-		line = if lineselect == -1 {
-			line - 1
-		} else if lineselect == height as i32 + 1 {
-			line + 1
-		} else {
-			line
-		};
-		lineselect = if lineselect == -1 {
-			0
-		} else if lineselect == height as i32 + 1 {
-			height as i32
-		} else {
-			lineselect
-		};
+			let mut ln = match novel.find(interactive(None).then_some(&config.picker)).await? {
+				Some(ln) => ln,
+				None => return Ok(()),
+			};
+
+			let chapters = match chapters {
+				Some(selection) => ln
+					.scrape_index()
+					.await?
+					.into_iter()
+					.filter(|chapter| selection.contains(chapter.get_chapter_number()))
+					.collect(),
+				None => {
+					ln.scrape().await?;
+					show_chapters(&ln).await?
+				}
+			};
 
-		let text = &chapter[line - 1..min(height + line - 1, chapter.len() - 1)];
+			let failed = download(&ln, chapters, &store).await?;
+			if !failed.is_empty() {
+				return Err(Failure::new(DOWNLOAD_FAILED, format!("{} chapters failed to download", failed.len())).into());
+			}
 
-		let attr = Attr {
-			fg: Color::RED,
-			..Attr::default()
-		};
+			return Ok(());
+		}
+		Some(Command::Export(export_args)) => return export(export_args, &config.export, &config.picker, &store).await,
+		Some(Command::Search { query, pages, format }) => return search(query, pages, format).await,
+		Some(Command::Browse(browse_args)) => return browse(browse_args).await,
+		Some(Command::Chapters {
+			novel,
+			chapters: selection,
+			format,
+		}) => return chapters(novel, selection, format, &config.picker).await,
+		Some(Command::Text { novel, chapter, format }) => return text(novel, chapter, format, &config.picker).await,
+		Some(Command::Library { format }) => return library(&store, format),
+		Some(Command::Update(update_args)) => {
+			if config.offline {
+				return Err("Lightnovels can't be updated in offline mode".into());
+			}
 
-		let mut row = 0;
-		for line in text {
-			let _ = term.print_with_attr(row, col, line, attr);
-			row += 1
+			return update(&store, update_args, &config.notify).await;
 		}
-		let _ = term.set_cursor(lineselect as usize, col);
-		let _ = term.present();
+		Some(Command::Cache { command }) => return cache(command, &store),
+		Some(Command::Config { command }) => return print_config(command, &config),
+		Some(Command::Read(read_args)) => return read_ln(read_args, &config).await,
+		None => {}
+	}
+
+	read_ln(args.read, &config).await
+}
+
+/// Lets the user pick a lightnovel and a chapter and reads it
+async fn read_ln(args: ReadArgs, config: &Config) -> Result<(), Box<dyn Error>> {
+	let mut bookmarks = Bookmarks::load()?;
+	let mut history = History::load()?;
+
+	let mut list = LightnovelList::new(args.category())?;
+	list.scrape().await?;
+
+	let mut ln = match show_ln(&mut list, &config.picker).await? {
+		Some(ln) => ln,
+		None => return Ok(()),
+	};
+
+	ln.scrape().await?;
+
+	let mut chapter = match show_chapters(&ln).await?.into_iter().next() {
+		Some(chapter) => chapter,
+		None => return Ok(()),
+	};
+	chapter.scrape().await?;
+
+	read(&ln, chapter, 0, &mut bookmarks, &mut history, &config.reader).await
+}
+
+pub fn data_dir() -> Result<PathBuf, Box<dyn Error>> {
+	Ok(dirs::data_dir().ok_or("Could not find the data directory")?.join("ln-cli"))
+}
+
+/// Writes the file through a temporary file next to it, so a failed write keeps the old content
+pub fn write_file(path: &Path, content: &str) -> Result<(), Box<dyn Error>> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	let temp = path.with_extension("part");
+	if let Err(err) = fs::write(&temp, content).and_then(|_| fs::rename(&temp, path)) {
+		let _ = fs::remove_file(&temp);
+		return Err(format!("Could not write {}: {}", path.display(), err).into());
 	}
 
 	Ok(())
@@ -1,122 +1,336 @@
+use std::sync::{Mutex, OnceLock};
 use std::{error::Error, mem, thread};
 
 use ln_lib::{Lightnovel, LightnovelChapter, LightnovelList};
+use serde::{Deserialize, Serialize};
 use skim::prelude::*;
+use tokio::runtime::Handle;
+use tokio_util::sync::CancellationToken;
+
+use crate::bookmark::{Bookmark, Bookmarks};
+
+/// How many paragraphs of a chapter the preview shows
+const PREVIEW_PARAGRAPHS: usize = 10;
+
+/// The `[picker]` settings of the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PickerConfig {
+	/// The most pages of lightnovels the picker loads, a page has about 24 lightnovels
+	pub max_pages: usize,
+}
+
+impl Default for PickerConfig {
+	fn default() -> Self {
+		Self { max_pages: 10 }
+	}
+}
 
 struct LightnovelWrapper {
 	pub ln: Lightnovel,
+	runtime: Handle,
+	/// The preview is only fetched once the lightnovel is looked at
+	preview: OnceLock<String>,
 }
 
 struct LightnovelChapterWarpper {
 	pub chapter: LightnovelChapter,
+	runtime: Handle,
+	preview: OnceLock<String>,
+}
+
+/// The header of the chapter picker with how many pages of the chapter index are loaded
+#[derive(Default)]
+struct Progress {
+	text: Mutex<String>,
+}
+
+struct BookmarkWrapper {
+	pub bookmark: Bookmark,
+	text: String,
 }
 
 impl SkimItem for LightnovelWrapper {
 	fn text(&self) -> Cow<str> {
 		Cow::Borrowed(self.ln.get_title())
 	}
+
+	fn preview(&self, _context: PreviewContext) -> ItemPreview {
+		let preview = cached(&self.preview, || self.runtime.block_on(ln_preview(self.ln.clone())));
+		ItemPreview::Text(preview)
+	}
 }
 
 impl SkimItem for LightnovelChapterWarpper {
 	fn text(&self) -> Cow<str> {
 		Cow::Borrowed(self.chapter.get_title())
 	}
+
+	fn preview(&self, _context: PreviewContext) -> ItemPreview {
+		let preview = cached(&self.preview, || self.runtime.block_on(chapter_preview(self.chapter.clone())));
+		ItemPreview::Text(preview)
+	}
+}
+
+impl LightnovelChapterWarpper {
+	fn new(chapter: LightnovelChapter, runtime: Handle) -> Self {
+		Self {
+			chapter,
+			runtime,
+			preview: OnceLock::new(),
+		}
+	}
+}
+
+/// The preview from the cache or else from the fetch, only a preview which could be fetched is cached
+fn cached(cache: &OnceLock<String>, fetch: impl FnOnce() -> Result<String, Box<dyn Error>>) -> String {
+	if let Some(preview) = cache.get() {
+		return preview.clone();
+	}
+
+	match fetch() {
+		Ok(preview) => cache.get_or_init(|| preview).clone(),
+		Err(err) => format!("The preview could not be loaded:\n{}", err),
+	}
+}
+
+/// The details and the latest chapter of the lightnovel
+async fn ln_preview(mut ln: Lightnovel) -> Result<String, Box<dyn Error>> {
+	ln.scrape().await?;
+	let details = ln.get_details().clone();
+
+	// The latest chapter is on the last page of the chapter index
+	if let Some(last_page) = ln.get_last_page().filter(|page| *page > 1) {
+		ln.open_scrape(last_page).await?;
+	}
+
+	let mut lines = vec![details.title.unwrap_or_else(|| ln.get_title().clone()), String::new()];
+	if !details.authors.is_empty() {
+		lines.push(format!("Author: {}", details.authors.join(", ")));
+	}
+	if !details.genres.is_empty() {
+		lines.push(format!("Genres: {}", details.genres.join(", ")));
+	}
+	if let Some(status) = details.status {
+		lines.push(format!("Status: {}", status));
+	}
+	if let Some(chapter) = ln.last() {
+		lines.push(format!("Latest chapter: {}", chapter.get_title()));
+	}
+
+	if !details.synopsis.is_empty() {
+		lines.push(String::new());
+		lines.push(details.synopsis.join("\n\n"));
+	}
+
+	Ok(lines.join("\n"))
+}
+
+/// The title and the first paragraphs of the chapter
+async fn chapter_preview(mut chapter: LightnovelChapter) -> Result<String, Box<dyn Error>> {
+	chapter.scrape().await?;
+
+	let mut paragraphs = vec![chapter.get_title().clone()];
+	paragraphs.extend(chapter.iter().take(PREVIEW_PARAGRAPHS).cloned());
+
+	Ok(paragraphs.join("\n\n"))
+}
+
+impl SkimItem for Progress {
+	fn text(&self) -> Cow<str> {
+		Cow::Owned(self.text.lock().unwrap().clone())
+	}
+}
+
+impl Progress {
+	fn set(&self, text: String) {
+		*self.text.lock().unwrap() = text;
+	}
 }
 
-pub fn show_ln(list: &mut LightnovelList) -> Option<Lightnovel> {
+impl SkimItem for BookmarkWrapper {
+	fn text(&self) -> Cow<str> {
+		Cow::Borrowed(&self.text)
+	}
+}
+
+/// Lets the user pick a lightnovel of the list, the next pages of the list are loaded while the picker is open
+pub async fn show_ln(list: &mut LightnovelList, config: &PickerConfig) -> Result<Option<Lightnovel>, Box<dyn Error>> {
 	let (tx_item, rx_item): (SkimItemSender, SkimItemReceiver) = unbounded();
 
-	let options = SkimOptionsBuilder::default()
-		.height(Some("100%"))
-		.prompt(Some("Select a lightnovel> "))
-		.reverse(true)
-		.build()
-		.unwrap();
+	let handle = thread::spawn(move || {
+		let options = SkimOptionsBuilder::default()
+			.height(Some("100%"))
+			.prompt(Some("Select a lightnovel> "))
+			.reverse(true)
+			.preview(Some(""))
+			.preview_window(Some("right:50%:wrap"))
+			.build()
+			.unwrap();
 
-	for mut ln in list.iter_mut() {
-		let wrapper = LightnovelWrapper { ln: mem::take(&mut ln) };
-		tx_item.send(Arc::new(wrapper)).unwrap();
+		Skim::run_with(&options, Some(rx_item))
+			.map(|out| out.selected_items)
+			.unwrap_or_else(|| Vec::new())
+	});
+
+	let runtime = Handle::current();
+	let mut pages = 1;
+	'pages: loop {
+		for mut ln in list.iter_mut() {
+			let wrapper = LightnovelWrapper {
+				ln: mem::take(&mut ln),
+				runtime: runtime.clone(),
+				preview: OnceLock::new(),
+			};
+
+			// The picker is closed
+			if tx_item.send(Arc::new(wrapper)).is_err() {
+				break 'pages;
+			}
+		}
+
+		if handle.is_finished() || pages >= config.max_pages || list.next_page().is_none() {
+			break;
+		}
+		// The lightnovels which were loaded can still be picked if a page fails
+		if list.scrape().await.is_err() {
+			break;
+		}
+		pages += 1;
 	}
 
 	drop(tx_item);
 
-	let selected_itemes = Skim::run_with(&options, Some(rx_item))
-		.map(|out| out.selected_items)
-		.unwrap_or_else(|| Vec::new());
+	let selected_itemes = handle.join().unwrap();
 
 	let ln_wrapper_pointer = selected_itemes.into_iter().next();
 
 	match ln_wrapper_pointer {
 		Some(ln_wrapper_pointer) => {
 			let ln_wrapper = (*ln_wrapper_pointer).as_any().downcast_ref::<LightnovelWrapper>().unwrap();
-			Some(ln_wrapper.ln.clone())
+			Ok(Some(ln_wrapper.ln.clone()))
 		}
-		None => None,
+		None => Ok(None),
 	}
 }
 
-pub async fn show_chapters(ln: &mut Lightnovel) -> Result<Option<LightnovelChapter>, Box<dyn Error>> {
+/// Sends the chapters of every page of the chapter index to the picker, starting at the page the lightnovel is on
+async fn load_chapters(mut ln: Lightnovel, tx_chapter: SkimItemSender, progress: Arc<Progress>) {
+	let runtime = Handle::current();
+	let pages = ln.get_last_page().unwrap_or(1);
+
+	loop {
+		for chapter in ln.iter() {
+			let wrapper = LightnovelChapterWarpper::new(chapter.clone(), runtime.clone());
+
+			// The picker is closed
+			if tx_chapter.send(Arc::new(wrapper)).is_err() {
+				return;
+			}
+		}
+		progress.set(format!("Loaded page {}/{} of the chapters", ln.get_page(), pages));
+
+		if ln.next_page().is_none() {
+			return;
+		}
+		if let Err(err) = ln.scrape().await {
+			progress.set(format!("Page {}/{} of the chapters could not be loaded: {}", ln.get_page(), pages, err));
+			return;
+		}
+	}
+}
+
+/// Lets the user pick chapters of the lightnovel, the next pages of the chapter index are loaded while the picker is open
+///
+/// The pages are loaded in a task which is cancelled with its requests once the picker closes.
+pub async fn show_chapters(ln: &Lightnovel) -> Result<Vec<LightnovelChapter>, Box<dyn Error>> {
 	let (tx_chapter, rx_chapter): (SkimItemSender, SkimItemReceiver) = unbounded();
 
-	let (tx, rx): (Sender<&str>, Receiver<&str>) = bounded(1);
+	let progress = Arc::new(Progress::default());
+	let token = CancellationToken::new();
 
-	let handle = thread::spawn(move || {
+	// The progress is the first item, so it becomes the header of the picker
+	tx_chapter.send(progress.clone()).unwrap();
+
+	let loader = tokio::spawn({
+		let token = token.clone();
+		let ln = ln.clone();
+		let progress = progress.clone();
+
+		async move {
+			tokio::select! {
+				_ = token.cancelled() => {}
+				_ = load_chapters(ln, tx_chapter, progress) => {}
+			}
+		}
+	});
+
+	let picker = tokio::task::spawn_blocking(move || {
 		let options = SkimOptionsBuilder::default()
 			.height(Some("100%"))
 			.reverse(true)
 			.prompt(Some("Select chapter> "))
+			.header_lines(1)
 			.multi(true)
+			.preview(Some(""))
+			.preview_window(Some("right:50%:wrap"))
 			.build()
 			.unwrap();
 
-		let ret = Skim::run_with(&options, Some(rx_chapter))
+		Skim::run_with(&options, Some(rx_chapter))
 			.map(|out| out.selected_items)
-			.unwrap_or_else(|| Vec::new());
+			.unwrap_or_else(|| Vec::new())
+	});
 
-		tx.send("done").unwrap();
+	let selected_itemes = picker.await;
+	token.cancel();
+	loader.await?;
+	let selected_itemes = selected_itemes?;
 
-		ret
-	});
+	// The header can't be selected, but only chapters are taken to be sure
+	let mut chapters = selected_itemes
+		.into_iter()
+		.filter_map(|chapter_wrapper_pointer| {
+			let chapter_wrapper = (*chapter_wrapper_pointer).as_any().downcast_ref::<LightnovelChapterWarpper>()?;
+			Some(chapter_wrapper.chapter.clone())
+		})
+		.collect::<Vec<_>>();
+	chapters.sort_by_key(|chapter| chapter.get_chapter_number());
 
-	loop {
-		for mut ch in ln.clone() {
-			let wrapper = LightnovelChapterWarpper { chapter: mem::take(&mut ch) };
+	Ok(chapters)
+}
 
-			if rx.try_recv().is_ok() {
-				break;
-			}
+pub fn show_bookmarks(bookmarks: &Bookmarks) -> Option<Bookmark> {
+	let (tx_item, rx_item): (SkimItemSender, SkimItemReceiver) = unbounded();
 
-			tx_chapter.send(Arc::new(wrapper)).unwrap();
-		}
-		let got_chapters = ln.next_scrape().await?;
-		if !got_chapters || rx.try_recv().is_ok() {
-			if !got_chapters {
-				for mut ch in ln.clone() {
-					let wrapper = LightnovelChapterWarpper { chapter: mem::take(&mut ch) };
-
-					if rx.try_recv().is_ok() {
-						break;
-					}
-
-					tx_chapter.send(Arc::new(wrapper)).unwrap();
-				}
-			}
-			break;
-		}
+	let options = SkimOptionsBuilder::default()
+		.height(Some("100%"))
+		.prompt(Some("Select a bookmark> "))
+		.reverse(true)
+		.build()
+		.unwrap();
+
+	for bookmark in bookmarks.iter() {
+		let wrapper = BookmarkWrapper {
+			text: bookmark.to_string(),
+			bookmark: bookmark.clone(),
+		};
+		tx_item.send(Arc::new(wrapper)).unwrap();
 	}
 
-	drop(tx_chapter);
+	drop(tx_item);
 
-	let selected_itemes = handle.join().unwrap();
-	let chapter_wrapper_pointer = selected_itemes.into_iter().next();
+	let selected_itemes = Skim::run_with(&options, Some(rx_item))
+		.map(|out| out.selected_items)
+		.unwrap_or_else(|| Vec::new());
 
-	let chapter = match chapter_wrapper_pointer {
-		Some(chapter_wrapper_pointer) => {
-			let chapter_wrapper = (*chapter_wrapper_pointer).as_any().downcast_ref::<LightnovelChapterWarpper>().unwrap();
-			Some(chapter_wrapper.chapter.clone())
+	let bookmark_wrapper_pointer = selected_itemes.into_iter().next();
+
+	match bookmark_wrapper_pointer {
+		Some(bookmark_wrapper_pointer) => {
+			let bookmark_wrapper = (*bookmark_wrapper_pointer).as_any().downcast_ref::<BookmarkWrapper>().unwrap();
+			Some(bookmark_wrapper.bookmark.clone())
 		}
 		None => None,
-	};
-
-	Ok(chapter)
+	}
 }
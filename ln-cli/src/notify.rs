@@ -0,0 +1,513 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::process::{self, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ln_lib::export::absolute_url;
+use ln_lib::{http_client, Lightnovel, LightnovelChapter};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::output::UpdateEntry;
+
+/// How long a notifier may take before it counts as failed
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A way to tell about new chapters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Notifier {
+	Webhook,
+	Command,
+	Email,
+}
+
+impl Display for Notifier {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		let name = match self {
+			Notifier::Webhook => "webhook",
+			Notifier::Command => "command",
+			Notifier::Email => "email",
+		};
+
+		f.write_str(name)
+	}
+}
+
+/// The `[notify]` settings of the config, who is told about the new chapters `update` finds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+	/// The notifiers of the lightnovels without their own notifiers, every notifier which is set up if not set
+	pub default: Option<Vec<Notifier>>,
+	/// The notifiers of single lightnovels by their title or url, an empty list tells nobody
+	pub novels: BTreeMap<String, Vec<Notifier>>,
+	/// The url the new chapters are posted to as JSON
+	pub webhook: Option<String>,
+	/// A shell command which is run with the new chapters in `LN_*` environment variables
+	pub command: Option<String>,
+	pub email: Option<EmailConfig>,
+}
+
+/// The `[notify.email]` settings, the mails are sent through an SMTP relay without TLS like a local mail server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+	/// The relay like `localhost:25`
+	pub server: String,
+	pub from: String,
+	pub to: Vec<String>,
+	/// Logs in with AUTH PLAIN if it is set, only to a relay on this machine since the password isn't encrypted
+	pub username: Option<String>,
+	pub password: Option<String>,
+}
+
+impl Default for EmailConfig {
+	fn default() -> Self {
+		Self {
+			server: "localhost:25".to_string(),
+			from: "ln-cli@localhost".to_string(),
+			to: Vec::new(),
+			username: None,
+			password: None,
+		}
+	}
+}
+
+impl NotifyConfig {
+	/// The notifiers of the lightnovel, the notifiers which aren't set up are left out
+	fn get_notifiers(&self, ln: &Lightnovel) -> Vec<Notifier> {
+		let notifiers = self
+			.novels
+			.iter()
+			.find(|(novel, _)| *novel == ln.get_title() || *novel == ln.get_url())
+			.map(|(_, notifiers)| notifiers.clone())
+			.or_else(|| self.default.clone())
+			.unwrap_or_else(|| vec![Notifier::Webhook, Notifier::Command, Notifier::Email]);
+
+		notifiers
+			.into_iter()
+			.filter(|notifier| match notifier {
+				Notifier::Webhook => self.webhook.is_some(),
+				Notifier::Command => self.command.is_some(),
+				Notifier::Email => self.email.is_some(),
+			})
+			.collect()
+	}
+}
+
+/// Tells the notifiers of every lightnovel with new chapters about them
+///
+/// A notifier which fails doesn't stop the others, the failures are returned.
+pub async fn notify(config: &NotifyConfig, updates: &[(Lightnovel, Vec<LightnovelChapter>)]) -> Vec<String> {
+	let mut failures = Vec::new();
+
+	for (ln, new) in updates.iter().filter(|(_, new)| !new.is_empty()) {
+		for notifier in config.get_notifiers(ln) {
+			let sent = match notifier {
+				Notifier::Webhook => timeout(NOTIFY_TIMEOUT, webhook(config, ln, new)).await,
+				Notifier::Command => timeout(NOTIFY_TIMEOUT, command(config, ln, new)).await,
+				Notifier::Email => timeout(NOTIFY_TIMEOUT, email(config, ln, new)).await,
+			};
+
+			let err = match sent {
+				Ok(Ok(())) => continue,
+				Ok(Err(err)) => err.to_string(),
+				Err(_) => format!("it took longer than {}s", NOTIFY_TIMEOUT.as_secs()),
+			};
+			failures.push(format!("{}: the {} notifier failed: {}", ln.get_title(), notifier, err));
+		}
+	}
+
+	failures
+}
+
+/// Posts the lightnovel and its new chapters as JSON like `update --format json` prints them
+async fn webhook(config: &NotifyConfig, ln: &Lightnovel, new: &[LightnovelChapter]) -> Result<(), Box<dyn Error>> {
+	let url = config.webhook.as_deref().unwrap_or_default();
+
+	let body = surf::Body::from_json(&UpdateEntry::new(ln, new)).map_err(|err| err.to_string())?;
+	let res = http_client()?.post(url).body(body).await.map_err(|err| err.to_string())?;
+
+	if !res.status().is_success() {
+		return Err(format!("{} answered with {}", url, res.status()).into());
+	}
+	Ok(())
+}
+
+/// Runs the command in the shell with the lightnovel and its newest chapter in environment variables
+async fn command(config: &NotifyConfig, ln: &Lightnovel, new: &[LightnovelChapter]) -> Result<(), Box<dyn Error>> {
+	let script = config.command.as_deref().unwrap_or_default();
+	let newest = new.last().ok_or("There are no new chapters")?;
+
+	let mut command = if cfg!(windows) {
+		let mut command = Command::new("cmd");
+		command.arg("/C").arg(script);
+		command
+	} else {
+		let mut command = Command::new("sh");
+		command.arg("-c").arg(script);
+		command
+	};
+
+	let status = command
+		.env("LN_NOVEL_TITLE", ln.get_title())
+		.env("LN_NOVEL_URL", absolute_url(ln.get_url()))
+		.env("LN_NEW_CHAPTERS", new.len().to_string())
+		.env("LN_NEWEST_CHAPTER_TITLE", newest.get_title())
+		.env("LN_NEWEST_CHAPTER_NUMBER", newest.get_chapter_number().to_string())
+		.env("LN_NEWEST_CHAPTER_URL", absolute_url(newest.get_url()))
+		.env("LN_UPDATE_JSON", serde_json::to_string(&UpdateEntry::new(ln, new))?)
+		.stdin(Stdio::null())
+		.status()
+		.await?;
+
+	if !status.success() {
+		return Err(format!("`{}` ended with {}", script, status).into());
+	}
+	Ok(())
+}
+
+/// Sends a mail with the new chapters through the SMTP relay
+async fn email(config: &NotifyConfig, ln: &Lightnovel, new: &[LightnovelChapter]) -> Result<(), Box<dyn Error>> {
+	let email = match &config.email {
+		Some(email) => email,
+		None => return Ok(()),
+	};
+	if email.to.is_empty() {
+		return Err("There is nobody in notify.email.to".into());
+	}
+
+	let mut body = format!("{} has {} new chapters:\r\n\r\n", ln.get_title(), new.len());
+	for chapter in new {
+		body += &format!(
+			"{}. {}\r\n{}\r\n",
+			chapter.get_chapter_number(),
+			chapter.get_title(),
+			absolute_url(chapter.get_url())
+		);
+	}
+
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	let domain = email.from.rsplit_once('@').map(|(_, domain)| domain).unwrap_or("localhost");
+	let message = format!(
+		"Date: {}\r\nMessage-ID: <{}.{}@{}>\r\nFrom: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n{}",
+		date(now.as_secs()),
+		now.as_nanos(),
+		process::id(),
+		domain,
+		email.from,
+		email.to.join(", "),
+		header(&format!("{}: {} new chapters", ln.get_title(), new.len())),
+		body
+	);
+
+	let mut smtp = Smtp::connect(&email.server).await?;
+	smtp.expect(220).await?;
+	smtp.send("EHLO localhost", 250).await?;
+
+	if let Some(username) = &email.username {
+		// Without TLS the password would cross the network in plain text
+		if !smtp.is_local()? {
+			return Err(
+				format!(
+					"The password for {} is only sent to an SMTP server on this machine, not to {}",
+					username, email.server
+				)
+				.into(),
+			);
+		}
+
+		let password = email.password.as_deref().unwrap_or_default();
+		let credentials = base64::encode(format!("\0{}\0{}", username, password));
+		smtp.send(&format!("AUTH PLAIN {}", credentials), 235).await?;
+	}
+
+	smtp.send(&format!("MAIL FROM:<{}>", email.from), 250).await?;
+	for to in &email.to {
+		smtp.send(&format!("RCPT TO:<{}>", to), 250).await?;
+	}
+	smtp.send("DATA", 354).await?;
+
+	// A line with a single dot ends the mail, so the dots at the start of the lines are doubled
+	let message = message
+		.split("\r\n")
+		.map(|line| if line.starts_with('.') { format!(".{}", line) } else { line.to_string() })
+		.collect::<Vec<_>>()
+		.join("\r\n");
+	smtp.send(&format!("{}\r\n.", message), 250).await?;
+	smtp.send("QUIT", 221).await?;
+
+	Ok(())
+}
+
+/// Writes the header in the encoded form of RFC 2047 if it isn't ASCII
+fn header(text: &str) -> String {
+	if text.is_ascii() {
+		text.to_string()
+	} else {
+		format!("=?UTF-8?B?{}?=", base64::encode(text))
+	}
+}
+
+/// The time of the unix timestamp in UTC like `Mon, 19 Oct 2026 07:09:08 +0000` as the `Date` header wants it
+fn date(secs: u64) -> String {
+	let (days, secs) = (secs / 86400, secs % 86400);
+	let weekday = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"][(days % 7) as usize];
+
+	let z = days + 719468;
+	let era = z / 146097;
+	let doe = z - era * 146097;
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+	let month = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"][month as usize - 1];
+
+	format!(
+		"{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+		weekday,
+		day,
+		month,
+		year,
+		secs / 3600,
+		secs % 3600 / 60,
+		secs % 60
+	)
+}
+
+/// A connection to an SMTP server
+struct Smtp {
+	stream: BufReader<TcpStream>,
+}
+
+impl Smtp {
+	async fn connect(server: &str) -> Result<Self, Box<dyn Error>> {
+		let stream = TcpStream::connect(server)
+			.await
+			.map_err(|err| format!("Could not connect to the SMTP server {}: {}", server, err))?;
+
+		Ok(Self {
+			stream: BufReader::new(stream),
+		})
+	}
+
+	/// Whether the server is on this machine
+	fn is_local(&self) -> Result<bool, Box<dyn Error>> {
+		Ok(self.stream.get_ref().peer_addr()?.ip().is_loopback())
+	}
+
+	/// Sends the line and reads the reply, the reply has to be in the class of the code like 2xx for 250
+	async fn send(&mut self, line: &str, code: u16) -> Result<(), Box<dyn Error>> {
+		self.stream.get_mut().write_all(format!("{}\r\n", line).as_bytes()).await?;
+		self.expect(code).await
+	}
+
+	/// Reads a reply, the lines of a reply with several lines have a `-` after the code except the last one
+	async fn expect(&mut self, code: u16) -> Result<(), Box<dyn Error>> {
+		loop {
+			let mut line = String::new();
+			if self.stream.read_line(&mut line).await? == 0 {
+				return Err("The SMTP server closed the connection".into());
+			}
+
+			let reply = line.get(..3).and_then(|reply| reply.parse::<u16>().ok());
+			if line.as_bytes().get(3) == Some(&b'-') {
+				continue;
+			}
+
+			return match reply {
+				Some(reply) if reply / 100 == code / 100 => Ok(()),
+				_ => Err(format!("The SMTP server answered: {}", line.trim_end()).into()),
+			};
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::{BufRead, BufReader as StdBufReader, Read, Write};
+	use std::net::TcpListener;
+	use std::sync::mpsc;
+	use std::thread;
+
+	fn update() -> (Lightnovel, Vec<LightnovelChapter>) {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let new = vec![
+			LightnovelChapter::new("Chapter 4".to_string(), "/novel/x/chapter-4".to_string(), 4),
+			LightnovelChapter::new("Chapter 5".to_string(), "/novel/x/chapter-5".to_string(), 5),
+		];
+		(ln, new)
+	}
+
+	/// Answers one SMTP session on a local port like a relay and sends the commands and the mail it got
+	fn relay() -> (String, mpsc::Receiver<Vec<String>>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let server = listener.local_addr().unwrap().to_string();
+		let (sender, receiver) = mpsc::channel();
+
+		thread::spawn(move || {
+			let (stream, _) = listener.accept().unwrap();
+			let mut writer = stream.try_clone().unwrap();
+			let mut lines = Vec::new();
+			let mut data = false;
+
+			writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+			for line in StdBufReader::new(stream).lines() {
+				let line = line.unwrap();
+				let reply: &[u8] = match line.as_str() {
+					"." if data => {
+						data = false;
+						b"250 queued\r\n"
+					}
+					_ if data => b"",
+					"EHLO localhost" => b"250-localhost\r\n250 AUTH PLAIN\r\n",
+					"DATA" => {
+						data = true;
+						b"354 go on\r\n"
+					}
+					"QUIT" => b"221 bye\r\n",
+					line if line.starts_with("AUTH PLAIN ") => b"235 ok\r\n",
+					_ => b"250 ok\r\n",
+				};
+				let quit = line == "QUIT";
+				lines.push(line);
+				writer.write_all(reply).unwrap();
+				if quit {
+					break;
+				}
+			}
+			sender.send(lines).unwrap();
+		});
+
+		(server, receiver)
+	}
+
+	#[tokio::test]
+	async fn email_sends_the_mail_with_its_headers_through_the_relay() {
+		let (server, lines) = relay();
+		let config = NotifyConfig {
+			email: Some(EmailConfig {
+				server,
+				from: "ln@example.com".to_string(),
+				to: vec!["reader@example.com".to_string()],
+				username: Some("reader".to_string()),
+				password: Some("secret".to_string()),
+			}),
+			..Default::default()
+		};
+		let (ln, new) = update();
+
+		email(&config, &ln, &new).await.unwrap();
+		let lines = lines.recv().unwrap();
+
+		assert!(lines.contains(&format!("AUTH PLAIN {}", base64::encode("\0reader\0secret"))));
+		assert!(lines.contains(&"RCPT TO:<reader@example.com>".to_string()));
+		assert!(lines.iter().any(|line| line.starts_with("Date: ") && line.ends_with(" +0000")));
+		assert!(lines
+			.iter()
+			.any(|line| line.starts_with("Message-ID: <") && line.ends_with("@example.com>")));
+		assert!(lines.contains(&"Subject: Novel: 2 new chapters".to_string()));
+		assert!(lines.contains(&"5. Chapter 5".to_string()));
+	}
+
+	#[test]
+	fn date_is_in_the_form_of_the_date_header() {
+		assert_eq!(date(0), "Thu, 01 Jan 1970 00:00:00 +0000");
+		assert_eq!(date(1_709_210_096), "Thu, 29 Feb 2024 12:34:56 +0000");
+	}
+
+	#[tokio::test]
+	async fn webhook_posts_the_update_as_json() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/hook", listener.local_addr().unwrap());
+		let (sender, requests) = mpsc::channel();
+
+		thread::spawn(move || {
+			for mut stream in listener.incoming().flatten() {
+				let mut request = Vec::new();
+				let mut buf = [0; 4096];
+				let complete = |request: &[u8]| {
+					let text = String::from_utf8_lossy(request);
+					match text.split_once("\r\n\r\n") {
+						Some((head, body)) => {
+							let length = head
+								.lines()
+								.find_map(|line| {
+									line
+										.to_lowercase()
+										.strip_prefix("content-length: ")
+										.map(|n| n.trim().parse().unwrap_or(0))
+								})
+								.unwrap_or(0);
+							body.len() >= length
+						}
+						None => false,
+					}
+				};
+				while !complete(&request) {
+					match stream.read(&mut buf) {
+						Ok(0) | Err(_) => break,
+						Ok(n) => request.extend_from_slice(&buf[..n]),
+					}
+				}
+				stream.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").unwrap();
+				sender.send(String::from_utf8_lossy(&request).to_string()).unwrap();
+			}
+		});
+
+		let config = NotifyConfig {
+			webhook: Some(url),
+			..Default::default()
+		};
+		let (ln, new) = update();
+		webhook(&config, &ln, &new).await.unwrap();
+
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("POST /hook "));
+		let json: serde_json::Value = serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+		assert_eq!(json["title"], "Novel");
+		assert_eq!(json["new_chapters"].as_array().unwrap().len(), 2);
+		assert!(requests.try_recv().is_err(), "the webhook is posted once");
+	}
+
+	#[cfg(unix)]
+	#[tokio::test]
+	async fn command_gets_the_update_in_the_environment() {
+		let path = std::env::temp_dir().join(format!("ln-cli-notify-{}", process::id()));
+		let config = NotifyConfig {
+			command: Some(format!("env | grep '^LN_' > '{}'", path.display())),
+			..Default::default()
+		};
+		let (ln, new) = update();
+
+		command(&config, &ln, &new).await.unwrap();
+		let env = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+
+		assert!(env.contains("LN_NOVEL_TITLE=Novel\n"));
+		assert!(env.contains("LN_NEW_CHAPTERS=2\n"));
+		assert!(env.contains("LN_NEWEST_CHAPTER_NUMBER=5\n"));
+		assert!(env.contains("LN_NEWEST_CHAPTER_TITLE=Chapter 5\n"));
+		assert!(env.contains("LN_NEWEST_CHAPTER_URL=https://readlightnovels.net/novel/x/chapter-5\n"));
+		assert!(env.contains("LN_UPDATE_JSON={"));
+	}
+
+	#[tokio::test]
+	async fn a_failing_command_is_a_failure() {
+		let config = NotifyConfig {
+			command: Some("exit 3".to_string()),
+			..Default::default()
+		};
+		let (ln, new) = update();
+
+		assert_eq!(notify(&config, &[(ln, new)]).await.len(), 1);
+	}
+}
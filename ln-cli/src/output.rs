@@ -0,0 +1,240 @@
+use std::io::{self, Write};
+
+use clap::ArgEnum;
+use ln_lib::{Lightnovel, LightnovelChapter};
+use serde::Serialize;
+
+/// How list results, chapter indexes and chapter text are printed to stdout
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+	/// readable lines, lightnovels are numbered for --index
+	Plain,
+	/// tab separated columns without a header
+	Tsv,
+	/// one JSON value
+	Json,
+}
+
+#[derive(Serialize)]
+struct NovelEntry<'a> {
+	title: &'a str,
+	url: &'a str,
+}
+
+#[derive(Serialize)]
+struct ChapterEntry<'a> {
+	number: usize,
+	title: &'a str,
+	url: &'a str,
+}
+
+#[derive(Serialize)]
+struct LibraryEntry<'a> {
+	title: &'a str,
+	url: &'a str,
+	downloaded: usize,
+	chapters: usize,
+}
+
+/// The new chapters of a lightnovel, the notifiers send it too
+#[derive(Serialize)]
+pub struct UpdateEntry<'a> {
+	title: &'a str,
+	url: &'a str,
+	new_chapters: Vec<ChapterEntry<'a>>,
+}
+
+impl<'a> UpdateEntry<'a> {
+	pub fn new(ln: &'a Lightnovel, new: &'a [LightnovelChapter]) -> Self {
+		Self {
+			title: ln.get_title(),
+			url: ln.get_url(),
+			new_chapters: new
+				.iter()
+				.map(|chapter| ChapterEntry {
+					number: chapter.get_chapter_number(),
+					title: chapter.get_title(),
+					url: chapter.get_url(),
+				})
+				.collect(),
+		}
+	}
+}
+
+#[derive(Serialize)]
+struct ChapterText<'a> {
+	number: usize,
+	title: &'a str,
+	url: &'a str,
+	paragraphs: &'a [String],
+}
+
+pub fn print_novels(novels: &[Lightnovel], format: OutputFormat) -> io::Result<()> {
+	let mut out = io::stdout().lock();
+
+	match format {
+		OutputFormat::Plain => {
+			for (i, ln) in novels.iter().enumerate() {
+				writeln!(out, "{:>4}. {}", i + 1, ln.get_title())?;
+			}
+		}
+		OutputFormat::Tsv => {
+			for ln in novels {
+				writeln!(out, "{}\t{}", tsv(ln.get_title()), tsv(ln.get_url()))?;
+			}
+		}
+		OutputFormat::Json => {
+			let entries = novels
+				.iter()
+				.map(|ln| NovelEntry {
+					title: ln.get_title(),
+					url: ln.get_url(),
+				})
+				.collect::<Vec<_>>();
+			json(&mut out, &entries)?;
+		}
+	}
+
+	out.flush()
+}
+
+pub fn print_chapters(chapters: &[LightnovelChapter], format: OutputFormat) -> io::Result<()> {
+	let mut out = io::stdout().lock();
+
+	match format {
+		OutputFormat::Plain => {
+			for chapter in chapters {
+				writeln!(out, "{:>6}. {}", chapter.get_chapter_number(), chapter.get_title())?;
+			}
+		}
+		OutputFormat::Tsv => {
+			for chapter in chapters {
+				writeln!(
+					out,
+					"{}\t{}\t{}",
+					chapter.get_chapter_number(),
+					tsv(chapter.get_title()),
+					tsv(chapter.get_url())
+				)?;
+			}
+		}
+		OutputFormat::Json => {
+			let entries = chapters
+				.iter()
+				.map(|chapter| ChapterEntry {
+					number: chapter.get_chapter_number(),
+					title: chapter.get_title(),
+					url: chapter.get_url(),
+				})
+				.collect::<Vec<_>>();
+			json(&mut out, &entries)?;
+		}
+	}
+
+	out.flush()
+}
+
+/// Prints the downloaded lightnovels with how many of their chapters are downloaded
+pub fn print_library(library: &[(Lightnovel, usize, usize)], format: OutputFormat) -> io::Result<()> {
+	let mut out = io::stdout().lock();
+
+	match format {
+		OutputFormat::Plain => {
+			for (ln, downloaded, chapters) in library {
+				writeln!(out, "{} ({}/{} chapters)", ln.get_title(), downloaded, chapters)?;
+			}
+		}
+		OutputFormat::Tsv => {
+			for (ln, downloaded, chapters) in library {
+				writeln!(out, "{}\t{}\t{}\t{}", tsv(ln.get_title()), tsv(ln.get_url()), downloaded, chapters)?;
+			}
+		}
+		OutputFormat::Json => {
+			let entries = library
+				.iter()
+				.map(|(ln, downloaded, chapters)| LibraryEntry {
+					title: ln.get_title(),
+					url: ln.get_url(),
+					downloaded: *downloaded,
+					chapters: *chapters,
+				})
+				.collect::<Vec<_>>();
+			json(&mut out, &entries)?;
+		}
+	}
+
+	out.flush()
+}
+
+/// Prints how many new chapters every lightnovel has and the title of the newest chapter
+pub fn print_updates(updates: &[(Lightnovel, Vec<LightnovelChapter>)], format: OutputFormat) -> io::Result<()> {
+	let mut out = io::stdout().lock();
+
+	match format {
+		OutputFormat::Plain => {
+			for (ln, new) in updates {
+				match new.last() {
+					Some(newest) => writeln!(
+						out,
+						"{}: {} new chapters, the newest is {}",
+						ln.get_title(),
+						new.len(),
+						newest.get_title()
+					)?,
+					None => writeln!(out, "{}: no new chapters", ln.get_title())?,
+				}
+			}
+		}
+		OutputFormat::Tsv => {
+			for (ln, new) in updates {
+				let newest = new.last().map(|chapter| tsv(chapter.get_title())).unwrap_or_default();
+				writeln!(out, "{}\t{}\t{}\t{}", tsv(ln.get_title()), tsv(ln.get_url()), new.len(), newest)?;
+			}
+		}
+		OutputFormat::Json => {
+			let entries = updates.iter().map(|(ln, new)| UpdateEntry::new(ln, new)).collect::<Vec<_>>();
+			json(&mut out, &entries)?;
+		}
+	}
+
+	out.flush()
+}
+
+pub fn print_text(chapter: &LightnovelChapter, format: OutputFormat) -> io::Result<()> {
+	let mut out = io::stdout().lock();
+
+	match format {
+		OutputFormat::Plain => {
+			writeln!(out, "{}\n", chapter.get_title())?;
+			for paragraph in chapter.iter() {
+				writeln!(out, "{}\n", paragraph)?;
+			}
+		}
+		OutputFormat::Tsv => {
+			for (i, paragraph) in chapter.iter().enumerate() {
+				writeln!(out, "{}\t{}\t{}", chapter.get_chapter_number(), i + 1, tsv(paragraph))?;
+			}
+		}
+		OutputFormat::Json => {
+			let text = ChapterText {
+				number: chapter.get_chapter_number(),
+				title: chapter.get_title(),
+				url: chapter.get_url(),
+				paragraphs: &chapter[..],
+			};
+			json(&mut out, &text)?;
+		}
+	}
+
+	out.flush()
+}
+
+fn json<W: Write, T: Serialize + ?Sized>(out: &mut W, value: &T) -> io::Result<()> {
+	serde_json::to_writer(&mut *out, value)?;
+	writeln!(out)
+}
+
+/// Escapes the characters that would break the columns
+fn tsv(text: &str) -> String {
+	text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
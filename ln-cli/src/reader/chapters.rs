@@ -0,0 +1,119 @@
+use ln_lib::{Lightnovel, LightnovelChapter, Store};
+use tokio::task::JoinHandle;
+
+/// The chapters of the lightnovel in the reader, the chapter after the one being read is loaded in the background
+pub struct Chapters {
+	ln: Lightnovel,
+	next: Option<Prefetch>,
+}
+
+struct Prefetch {
+	/// The number of the chapter before the prefetched one
+	after: usize,
+	handle: JoinHandle<(Lightnovel, Result<Option<LightnovelChapter>, String>)>,
+}
+
+impl Chapters {
+	pub fn new(ln: Lightnovel) -> Self {
+		Self { ln, next: None }
+	}
+
+	pub fn get_lightnovel(&self) -> &Lightnovel {
+		&self.ln
+	}
+
+	/// Starts loading the chapter after the chapter with the number
+	pub fn prefetch(&mut self, number: usize) {
+		self.abort();
+
+		let mut ln = self.ln.clone();
+		let handle = tokio::spawn(async move {
+			let chapter = load(&mut ln, number, true).await;
+			(ln, chapter)
+		});
+
+		self.next = Some(Prefetch { after: number, handle });
+	}
+
+	/// Loads the chapter after or before the chapter with the number, it is `None` at the first or last chapter
+	pub async fn load(&mut self, number: usize, forward: bool) -> Result<Option<LightnovelChapter>, String> {
+		if forward {
+			if let Some(prefetch) = self.next.take().filter(|prefetch| prefetch.after == number) {
+				if let Ok((ln, Ok(chapter))) = prefetch.handle.await {
+					self.ln = ln;
+					return Ok(chapter);
+				}
+			}
+		}
+		self.abort();
+
+		load(&mut self.ln, number, forward).await
+	}
+
+	fn abort(&mut self) {
+		if let Some(prefetch) = self.next.take() {
+			prefetch.handle.abort();
+		}
+	}
+}
+
+impl Drop for Chapters {
+	fn drop(&mut self) {
+		self.abort();
+	}
+}
+
+/// Finds the chapter after or before the chapter with the number in the index and scrapes its paragraphs
+async fn load(ln: &mut Lightnovel, number: usize, forward: bool) -> Result<Option<LightnovelChapter>, String> {
+	let mut chapter = match adjacent(ln, number, forward).await? {
+		Some(chapter) => chapter,
+		None => return Ok(None),
+	};
+
+	match chapter.scrape().await {
+		Ok(_) => Ok(Some(chapter)),
+		Err(err) => Err(err.to_string()),
+	}
+}
+
+/// Opens the pages of the chapter index until it finds the chapter next to the chapter with the number
+///
+/// Online the chapters are numbered by their position in the index, so the page is known right away.
+/// Offline only the downloaded chapters are in the index, so the pages are walked instead.
+async fn adjacent(ln: &mut Lightnovel, number: usize, forward: bool) -> Result<Option<LightnovelChapter>, String> {
+	if ln.is_empty() {
+		ln.scrape().await.map_err(|err| err.to_string())?;
+	}
+
+	let target = if forward { number + 1 } else { number.saturating_sub(1) };
+	if !Store::is_offline() && target > 0 {
+		let page = Lightnovel::page_of(target);
+		if page != ln.get_page() && ln.open_page(page).is_some() {
+			ln.scrape().await.map_err(|err| err.to_string())?;
+		}
+	}
+
+	let first = |ln: &Lightnovel| ln.first().map(|chapter| chapter.get_chapter_number()).unwrap_or(0);
+	let last = |ln: &Lightnovel| ln.last().map(|chapter| chapter.get_chapter_number()).unwrap_or(0);
+
+	// The index is sorted, so the first walk can't overshoot what the second walk looks for
+	if forward {
+		while first(ln) > number && ln.prev_page().is_some() {
+			ln.scrape().await.map_err(|err| err.to_string())?;
+		}
+		while last(ln) <= number && ln.next_page().is_some() {
+			ln.scrape().await.map_err(|err| err.to_string())?;
+		}
+
+		Ok(ln.iter().find(|chapter| chapter.get_chapter_number() > number).cloned())
+	} else {
+		while last(ln) < number && ln.next_page().is_some() {
+			ln.scrape().await.map_err(|err| err.to_string())?;
+		}
+		while first(ln) >= number && ln.prev_page().is_some() {
+			ln.scrape().await.map_err(|err| err.to_string())?;
+		}
+
+		Ok(ln.iter().rev().find(|chapter| chapter.get_chapter_number() < number).cloned())
+	}
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tuikit::event::Key;
+
+/// What a key does in the reader
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+	LineDown,
+	LineUp,
+	HalfPageDown,
+	HalfPageUp,
+	PageDown,
+	PageUp,
+	Top,
+	Bottom,
+	/// Goes one page down or to the next chapter at the end of the chapter
+	Advance,
+	/// Goes one page up or to the previous chapter at the start of the chapter
+	Retreat,
+	NextChapter,
+	PrevChapter,
+	Search,
+	SearchBackward,
+	NextMatch,
+	PrevMatch,
+	Bookmark,
+	Highlight,
+	ToggleTheme,
+	OpenInBrowser,
+	Help,
+	/// Ends the search
+	Cancel,
+	Quit,
+}
+
+impl Action {
+	pub const ALL: [Action; 23] = [
+		Action::LineDown,
+		Action::LineUp,
+		Action::HalfPageDown,
+		Action::HalfPageUp,
+		Action::PageDown,
+		Action::PageUp,
+		Action::Top,
+		Action::Bottom,
+		Action::Advance,
+		Action::Retreat,
+		Action::NextChapter,
+		Action::PrevChapter,
+		Action::Search,
+		Action::SearchBackward,
+		Action::NextMatch,
+		Action::PrevMatch,
+		Action::Bookmark,
+		Action::Highlight,
+		Action::ToggleTheme,
+		Action::OpenInBrowser,
+		Action::Help,
+		Action::Cancel,
+		Action::Quit,
+	];
+
+	pub fn description(&self) -> &'static str {
+		match self {
+			Action::LineDown => "one line down",
+			Action::LineUp => "one line up",
+			Action::HalfPageDown => "half a page down",
+			Action::HalfPageUp => "half a page up",
+			Action::PageDown => "one page down",
+			Action::PageUp => "one page up",
+			Action::Top => "start of the chapter",
+			Action::Bottom => "end of the chapter",
+			Action::Advance => "one page down, the next chapter at the end",
+			Action::Retreat => "one page up, the previous chapter at the start",
+			Action::NextChapter => "next chapter",
+			Action::PrevChapter => "previous chapter",
+			Action::Search => "search down",
+			Action::SearchBackward => "search up",
+			Action::NextMatch => "next match",
+			Action::PrevMatch => "previous match",
+			Action::Bookmark => "bookmark the paragraph",
+			Action::Highlight => "highlight the paragraph with a note",
+			Action::ToggleTheme => "next theme",
+			Action::OpenInBrowser => "open the chapter in the browser",
+			Action::Help => "show the key bindings",
+			Action::Cancel => "end the search",
+			Action::Quit => "quit",
+		}
+	}
+}
+
+/// The key bindings a config starts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPreset {
+	#[default]
+	Default,
+	Vim,
+	Less,
+}
+
+/// One action or a list of actions, the first action which can be done is done
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Actions {
+	One(Action),
+	Many(Vec<Action>),
+}
+
+impl Actions {
+	fn into_vec(self) -> Vec<Action> {
+		match self {
+			Actions::One(action) => vec![action],
+			Actions::Many(actions) => actions,
+		}
+	}
+}
+
+/// The actions of the keys in the reader
+#[derive(Debug, Clone)]
+pub struct Bindings {
+	keys: Vec<(Key, Vec<Action>)>,
+}
+
+impl Bindings {
+	/// The bindings of the preset with the bindings of the config on top, a key bound to no actions is unbound
+	pub fn new(preset: KeyPreset, overrides: &BTreeMap<String, Actions>) -> Result<Self, String> {
+		let mut keys = preset
+			.bindings()
+			.into_iter()
+			.map(|(name, actions)| (parse_key(name).unwrap(), actions.to_vec()))
+			.collect::<Vec<_>>();
+
+		for (name, actions) in overrides {
+			let key = parse_key(name).ok_or_else(|| format!("{} is not a key, the bindings use keys like j, G, ctrl-d, pgdn or f1", name))?;
+			keys.retain(|(k, _)| *k != key);
+			keys.push((key, actions.clone().into_vec()));
+		}
+		keys.retain(|(_, actions)| !actions.is_empty());
+
+		Ok(Self { keys })
+	}
+
+	pub fn get(&self, key: &Key) -> &[Action] {
+		self
+			.keys
+			.iter()
+			.find(|(k, _)| k == key)
+			.map(|(_, actions)| actions.as_slice())
+			.unwrap_or_default()
+	}
+
+	/// The names of the keys bound to the action
+	pub fn keys(&self, action: Action) -> Vec<String> {
+		self
+			.keys
+			.iter()
+			.filter(|(_, actions)| actions.contains(&action))
+			.map(|(key, _)| key_name(key))
+			.collect()
+	}
+}
+
+impl KeyPreset {
+	fn bindings(&self) -> Vec<(&'static str, &'static [Action])> {
+		use Action::*;
+
+		let mut bindings: Vec<(&'static str, &'static [Action])> = vec![
+			("down", &[LineDown]),
+			("up", &[LineUp]),
+			("pgdn", &[PageDown]),
+			("pgup", &[PageUp]),
+			("space", &[PageDown]),
+			("home", &[Top]),
+			("end", &[Bottom]),
+			("right", &[Advance]),
+			("left", &[Retreat]),
+			("/", &[Search]),
+			("?", &[SearchBackward]),
+			("f1", &[Help]),
+			("t", &[ToggleTheme]),
+			("o", &[OpenInBrowser]),
+			("esc", &[Cancel, Quit]),
+			("q", &[Quit]),
+		];
+
+		bindings.extend(match self {
+			KeyPreset::Default => vec![
+				("n", &[NextMatch, NextChapter] as &[Action]),
+				("N", &[PrevMatch]),
+				("p", &[PrevChapter]),
+				("b", &[Bookmark]),
+				("h", &[Highlight]),
+			],
+			KeyPreset::Vim => vec![
+				("j", &[LineDown] as &[Action]),
+				("k", &[LineUp]),
+				("ctrl-e", &[LineDown]),
+				("ctrl-y", &[LineUp]),
+				("ctrl-d", &[HalfPageDown]),
+				("ctrl-u", &[HalfPageUp]),
+				("ctrl-f", &[PageDown]),
+				("ctrl-b", &[PageUp]),
+				("g", &[Top]),
+				("G", &[Bottom]),
+				("l", &[Advance]),
+				("h", &[Retreat]),
+				("]", &[NextChapter]),
+				("[", &[PrevChapter]),
+				("n", &[NextMatch]),
+				("N", &[PrevMatch]),
+				("m", &[Bookmark]),
+				("v", &[Highlight]),
+			],
+			KeyPreset::Less => vec![
+				("j", &[LineDown] as &[Action]),
+				("e", &[LineDown]),
+				("enter", &[LineDown]),
+				("ctrl-n", &[LineDown]),
+				("k", &[LineUp]),
+				("y", &[LineUp]),
+				("ctrl-p", &[LineUp]),
+				("d", &[HalfPageDown]),
+				("u", &[HalfPageUp]),
+				("f", &[PageDown]),
+				("ctrl-f", &[PageDown]),
+				("ctrl-v", &[PageDown]),
+				("b", &[PageUp]),
+				("ctrl-b", &[PageUp]),
+				("g", &[Top]),
+				("<", &[Top]),
+				("G", &[Bottom]),
+				(">", &[Bottom]),
+				("]", &[NextChapter]),
+				("[", &[PrevChapter]),
+				("n", &[NextMatch]),
+				("N", &[PrevMatch]),
+				("m", &[Bookmark]),
+				("v", &[Highlight]),
+				("h", &[Help]),
+			],
+		});
+
+		bindings
+	}
+}
+
+/// Reads a key like `j`, `G`, `ctrl-d`, `alt-x`, `space`, `pgdn` or `f1`
+pub fn parse_key(name: &str) -> Option<Key> {
+	let mut chars = name.chars();
+	if let (Some(c), None) = (chars.next(), chars.next()) {
+		return Some(Key::Char(c));
+	}
+
+	let lower = name.to_lowercase();
+	let single = |rest: &str| {
+		let mut chars = rest.chars();
+		match (chars.next(), chars.next()) {
+			(Some(c), None) => Some(c),
+			_ => None,
+		}
+	};
+
+	if let Some(c) = lower.strip_prefix("ctrl-").and_then(single) {
+		return Some(Key::Ctrl(c));
+	}
+	if let Some(c) = name.get(4..).filter(|_| lower.starts_with("alt-")).and_then(single) {
+		return Some(Key::Alt(c));
+	}
+	if let Some(n) = lower
+		.strip_prefix('f')
+		.and_then(|n| n.parse::<u8>().ok())
+		.filter(|n| (1..=12).contains(n))
+	{
+		return Some(Key::F(n));
+	}
+
+	let key = match lower.as_str() {
+		"space" => Key::Char(' '),
+		"enter" => Key::Enter,
+		"esc" => Key::ESC,
+		"tab" => Key::Tab,
+		"backspace" | "bs" => Key::Backspace,
+		"del" | "delete" => Key::Delete,
+		"up" => Key::Up,
+		"down" => Key::Down,
+		"left" => Key::Left,
+		"right" => Key::Right,
+		"home" => Key::Home,
+		"end" => Key::End,
+		"pgup" | "page-up" => Key::PageUp,
+		"pgdn" | "page-down" => Key::PageDown,
+		_ => return None,
+	};
+
+	Some(key)
+}
+
+/// The name of the key like it is written in the config
+pub fn key_name(key: &Key) -> String {
+	match key {
+		Key::Char(' ') => "space".to_string(),
+		Key::Char(c) => c.to_string(),
+		Key::Ctrl(c) => format!("ctrl-{}", c),
+		Key::Alt(c) => format!("alt-{}", c),
+		Key::F(n) => format!("f{}", n),
+		Key::Enter => "enter".to_string(),
+		Key::ESC => "esc".to_string(),
+		Key::Tab => "tab".to_string(),
+		Key::Backspace => "backspace".to_string(),
+		Key::Delete => "del".to_string(),
+		Key::Up => "up".to_string(),
+		Key::Down => "down".to_string(),
+		Key::Left => "left".to_string(),
+		Key::Right => "right".to_string(),
+		Key::Home => "home".to_string(),
+		Key::End => "end".to_string(),
+		Key::PageUp => "pgup".to_string(),
+		Key::PageDown => "pgdn".to_string(),
+		key => format!("{:?}", key).to_lowercase(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_key_reads_chars_modifiers_and_named_keys() {
+		assert_eq!(parse_key("j"), Some(Key::Char('j')));
+		assert_eq!(parse_key("G"), Some(Key::Char('G')));
+		assert_eq!(parse_key("Ctrl-D"), Some(Key::Ctrl('d')));
+		assert_eq!(parse_key("alt-X"), Some(Key::Alt('X')));
+		assert_eq!(parse_key("f12"), Some(Key::F(12)));
+		assert_eq!(parse_key("space"), Some(Key::Char(' ')));
+		assert_eq!(parse_key("PgDn"), Some(Key::PageDown));
+		assert_eq!(parse_key("f13"), None);
+		assert_eq!(parse_key("ctrl-dd"), None);
+		assert_eq!(parse_key("nothing"), None);
+	}
+
+	#[test]
+	fn key_name_is_read_back_by_parse_key() {
+		for key in [Key::Char(' '), Key::Ctrl('d'), Key::Alt('x'), Key::F(1), Key::PageUp, Key::ESC] {
+			assert_eq!(parse_key(&key_name(&key)), Some(key));
+		}
+	}
+
+	#[test]
+	fn every_preset_searches_backward_with_a_question_mark() {
+		for preset in [KeyPreset::Default, KeyPreset::Vim, KeyPreset::Less] {
+			let bindings = Bindings::new(preset, &BTreeMap::new()).unwrap();
+			assert_eq!(bindings.get(&Key::Char('?')), &[Action::SearchBackward]);
+			assert_eq!(bindings.get(&Key::F(1)), &[Action::Help]);
+		}
+		let less = Bindings::new(KeyPreset::Less, &BTreeMap::new()).unwrap();
+		assert_eq!(less.get(&Key::Char('h')), &[Action::Help]);
+	}
+
+	#[test]
+	fn overrides_replace_and_unbind_keys() {
+		let overrides = BTreeMap::from([
+			("j".to_string(), Actions::One(Action::NextChapter)),
+			("q".to_string(), Actions::Many(Vec::new())),
+			("ctrl-x".to_string(), Actions::Many(vec![Action::Cancel, Action::Quit])),
+		]);
+		let bindings = Bindings::new(KeyPreset::Vim, &overrides).unwrap();
+
+		assert_eq!(bindings.get(&Key::Char('j')), &[Action::NextChapter]);
+		assert!(bindings.get(&Key::Char('q')).is_empty());
+		assert_eq!(bindings.get(&Key::Ctrl('x')), &[Action::Cancel, Action::Quit]);
+		assert_eq!(bindings.keys(Action::LineDown), vec!["down", "ctrl-e"]);
+		assert!(!bindings.keys(Action::Quit).contains(&"q".to_string()));
+
+		let wrong = BTreeMap::from([("hyper-j".to_string(), Actions::One(Action::Quit))]);
+		assert!(Bindings::new(KeyPreset::Default, &wrong).is_err());
+	}
+}
@@ -0,0 +1,258 @@
+use std::ops::Range;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// A line on the screen, it shows a part of a paragraph or of the chapter title
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+	pub paragraph: usize,
+	/// The bytes of the paragraph on this line, empty for the space between lines
+	pub range: Range<usize>,
+	/// If this is the first line of the paragraph
+	pub first: bool,
+	/// If this line ends the paragraph or a line break, it isn't justified
+	pub last: bool,
+	/// If this line shows the chapter title instead of a paragraph
+	pub header: bool,
+}
+
+/// Wraps the title and the paragraphs into lines which are at most `width` columns wide
+pub fn layout(header: &str, paragraphs: &[String], width: usize, line_spacing: usize, paragraph_spacing: usize) -> Vec<Line> {
+	let mut lines = Vec::new();
+
+	if !header.is_empty() {
+		push_text(&mut lines, header, 0, true, width, line_spacing);
+		push_blank(&mut lines, paragraph_spacing.max(1));
+	}
+
+	for (paragraph, text) in paragraphs.iter().enumerate() {
+		if paragraph > 0 {
+			push_blank(&mut lines, paragraph_spacing);
+		}
+		push_text(&mut lines, text, paragraph, false, width, line_spacing);
+	}
+
+	lines
+}
+
+fn push_text(lines: &mut Vec<Line>, text: &str, paragraph: usize, header: bool, width: usize, line_spacing: usize) {
+	let mut offset = 0;
+	let mut first = true;
+
+	for hard_line in text.split('\n') {
+		let ranges = wrap(hard_line, width);
+		let count = ranges.len();
+
+		for (i, range) in ranges.into_iter().enumerate() {
+			if !first {
+				push_blank(lines, line_spacing);
+			}
+			lines.push(Line {
+				paragraph,
+				range: offset + range.start..offset + range.end,
+				first,
+				last: i + 1 == count,
+				header,
+			});
+			first = false;
+		}
+		offset += hard_line.len() + 1;
+	}
+}
+
+/// Adds empty lines which belong to the paragraph of the line before them
+fn push_blank(lines: &mut Vec<Line>, count: usize) {
+	if let Some(line) = lines.last().cloned() {
+		for _ in 0..count {
+			lines.push(Line {
+				range: line.range.end..line.range.end,
+				first: false,
+				last: true,
+				..line.clone()
+			});
+		}
+	}
+}
+
+/// Where the spaces of the line are stretched so it fills the width, the byte after each gap and how many
+/// columns are added there
+pub fn justify(text: &str, width: usize) -> Vec<(usize, usize)> {
+	let gaps = text
+		.char_indices()
+		.zip(text.chars().skip(1))
+		.filter(|((_, c), next)| *c == ' ' && *next != ' ')
+		.map(|((i, _), _)| i + 1)
+		.collect::<Vec<_>>();
+
+	let extra = width.saturating_sub(text.width());
+	if gaps.is_empty() || extra == 0 {
+		return Vec::new();
+	}
+
+	gaps
+		.iter()
+		.enumerate()
+		.map(|(i, gap)| (*gap, extra / gaps.len() + usize::from(i < extra % gaps.len())))
+		.collect()
+}
+
+/// The line with its stretched spaces
+pub fn stretch(text: &str, gaps: &[(usize, usize)]) -> String {
+	let mut stretched = String::with_capacity(text.len());
+	let mut start = 0;
+
+	for (gap, extra) in gaps {
+		stretched.push_str(&text[start..*gap]);
+		stretched.push_str(&" ".repeat(*extra));
+		start = *gap;
+	}
+	stretched.push_str(&text[start..]);
+
+	stretched
+}
+
+/// The column of the byte of the line after its spaces were stretched
+pub fn column(text: &str, gaps: &[(usize, usize)], offset: usize) -> usize {
+	text[..offset].width() + gaps.iter().filter(|(gap, _)| *gap <= offset).map(|(_, extra)| extra).sum::<usize>()
+}
+
+/// Breaks the text at spaces, wide characters like CJK can be broken anywhere and words longer than the
+/// width are split
+pub fn wrap(text: &str, width: usize) -> Vec<Range<usize>> {
+	let width = width.max(2);
+	let mut lines = Vec::new();
+
+	let mut start = 0;
+	let mut end = 0;
+	let mut line_width = 0;
+
+	for (at, segment) in segments(text) {
+		let segment_width = segment.width();
+
+		if segment.starts_with(char::is_whitespace) {
+			if end <= start {
+				start = at + segment.len();
+			} else {
+				line_width += segment_width;
+			}
+			continue;
+		}
+
+		if end > start && line_width + segment_width > width {
+			lines.push(start..end);
+			start = at;
+			line_width = 0;
+		}
+
+		if end <= start {
+			start = at;
+		}
+
+		for (i, c) in segment.char_indices() {
+			let char_width = c.width().unwrap_or(0);
+			if line_width > 0 && line_width + char_width > width {
+				lines.push(start..at + i);
+				start = at + i;
+				line_width = 0;
+			}
+			line_width += char_width;
+		}
+		end = at + segment.len();
+	}
+
+	if end > start || lines.is_empty() {
+		lines.push(start..end.max(start));
+	}
+
+	lines
+}
+
+/// Splits the text into runs of whitespace, words and single wide characters
+fn segments(text: &str) -> Vec<(usize, &str)> {
+	let mut segments = Vec::new();
+	let mut start = 0;
+	let mut kind = None;
+
+	for (i, c) in text.char_indices() {
+		let current = if c.is_whitespace() {
+			0
+		} else if c.width().unwrap_or(0) > 1 {
+			1
+		} else {
+			2
+		};
+
+		// Every wide character is a segment of its own
+		if kind.is_some() && (kind != Some(current) || current == 1) {
+			segments.push((start, &text[start..i]));
+			start = i;
+		}
+		kind = Some(current);
+	}
+
+	if start < text.len() {
+		segments.push((start, &text[start..]));
+	}
+
+	segments
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn wrapped(text: &str, width: usize) -> Vec<&str> {
+		wrap(text, width).into_iter().map(|range| &text[range]).collect()
+	}
+
+	#[test]
+	fn wrap_breaks_at_spaces() {
+		assert_eq!(wrapped("hello world foo", 11), vec!["hello world", "foo"]);
+		assert_eq!(wrapped("  leading", 20), vec!["leading"]);
+		assert_eq!(wrap("", 10), vec![0..0]);
+	}
+
+	#[test]
+	fn wrap_splits_long_words_and_wide_characters() {
+		assert_eq!(wrapped("abcdefgh", 3), vec!["abc", "def", "gh"]);
+		assert_eq!(wrapped("日本語", 4), vec!["日本", "語"]);
+	}
+
+	#[test]
+	fn layout_puts_the_spacing_between_the_lines() {
+		let lines = layout("Title", &["one two".to_string(), "three".to_string()], 5, 0, 1);
+
+		let shown = lines
+			.iter()
+			.map(|line| (line.paragraph, line.range.clone(), line.header))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			shown,
+			vec![
+				(0, 0..5, true),
+				(0, 5..5, true),
+				(0, 0..3, false),
+				(0, 4..7, false),
+				(0, 7..7, false),
+				(1, 0..5, false)
+			]
+		);
+		assert!(lines[2].first && !lines[2].last && lines[3].last);
+	}
+
+	#[test]
+	fn justify_spreads_the_extra_columns_over_the_gaps() {
+		assert_eq!(justify("a b c", 8), vec![(2, 2), (4, 1)]);
+		assert_eq!(justify("a  b", 6), vec![(3, 2)]);
+		assert!(justify("abc", 10).is_empty());
+		assert!(justify("a b c", 5).is_empty());
+	}
+
+	#[test]
+	fn stretch_and_column_follow_the_gaps() {
+		let gaps = justify("a b c", 8);
+		assert_eq!(stretch("a b c", &gaps), "a   b  c");
+		assert_eq!(column("a b c", &gaps, 4), 7);
+		assert_eq!(column("a b c", &gaps, 0), 0);
+	}
+}
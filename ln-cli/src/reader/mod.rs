@@ -0,0 +1,682 @@
+mod chapters;
+mod keys;
+mod layout;
+mod search;
+mod theme;
+mod tui;
+
+use std::cmp::{max, min};
+use std::error::Error;
+
+use ln_lib::export::absolute_url;
+use ln_lib::{Lightnovel, LightnovelChapter};
+use tuikit::event::{Event, Key};
+use tuikit::term::{Term, TermHeight, TermOptions};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::bookmark::{Bookmark, Bookmarks, MarkKind};
+use crate::history::History;
+use chapters::Chapters;
+use keys::{Action, Bindings};
+use layout::{column, justify, layout, stretch, Line};
+use search::Search;
+pub use theme::ReaderConfig;
+use theme::Theme;
+pub use tui::tui;
+
+const TEXT_COL: usize = 2;
+/// How many lines one step of the mouse wheel scrolls
+const WHEEL_LINES: usize = 3;
+
+pub async fn read(
+	ln: &Lightnovel, chapter: LightnovelChapter, start: usize, bookmarks: &mut Bookmarks, history: &mut History, config: &ReaderConfig,
+) -> Result<(), Box<dyn Error>> {
+	if bookmarks.reanchor(&chapter) {
+		bookmarks.save()?;
+	}
+
+	let bindings = Bindings::new(config.keys, &config.bindings)?;
+	let term: Term<()> = Term::with_options(TermOptions::default().height(TermHeight::Percent(100)).mouse_enabled(true)).unwrap();
+
+	let mut chapters = Chapters::new(ln.clone());
+	chapters.prefetch(chapter.get_chapter_number());
+
+	let mut reader = Reader::new(ln.get_title().clone(), chapter, config);
+	let (width, height) = term.term_size().unwrap_or((80, 24));
+	reader.resize(width, height);
+	reader.jump(start);
+
+	loop {
+		reader.mark_read(history)?;
+		reader.draw(&term, bookmarks);
+
+		let ev = match term.poll_event() {
+			Ok(ev) => ev,
+			Err(_) => break,
+		};
+		reader.message = None;
+
+		let key = match ev {
+			Event::Key(Key::WheelUp(_, _, count)) => {
+				reader.scroll(-((count as usize * WHEEL_LINES) as isize));
+				continue;
+			}
+			Event::Key(Key::WheelDown(_, _, count)) => {
+				reader.scroll((count as usize * WHEEL_LINES) as isize);
+				continue;
+			}
+			Event::Resize { width, height } => {
+				reader.resize(width, height);
+				continue;
+			}
+			Event::Key(key) => key,
+			_ => continue,
+		};
+
+		// The first action of the key which can be done right now
+		let action = bindings.get(&key).iter().copied().find(|action| reader.can(*action));
+
+		let forward = match reader.act(action, &term, chapters.get_lightnovel(), bookmarks)? {
+			Step::Stay => continue,
+			Step::Quit => break,
+			Step::Chapter(forward) => forward,
+			Step::Help => {
+				reader.draw_help(&term, &bindings);
+				// Any key closes the help
+				while let Ok(ev) = term.poll_event() {
+					match ev {
+						Event::Resize { width, height } => {
+							reader.resize(width, height);
+							reader.draw(&term, bookmarks);
+							reader.draw_help(&term, &bindings);
+						}
+						Event::Key(_) => break,
+						_ => {}
+					}
+				}
+				continue;
+			}
+		};
+
+		let number = reader.chapter.get_chapter_number();
+
+		reader.message = Some(
+			if forward {
+				"Loading the next chapter..."
+			} else {
+				"Loading the previous chapter..."
+			}
+			.to_string(),
+		);
+		reader.draw(&term, bookmarks);
+
+		match chapters.load(number, forward).await {
+			Ok(Some(chapter)) => {
+				if bookmarks.reanchor(&chapter) {
+					bookmarks.save()?;
+				}
+				chapters.prefetch(chapter.get_chapter_number());
+
+				reader.message = None;
+				reader.set_chapter(chapter);
+				// Going back continues reading at the end of the previous chapter
+				if !forward {
+					reader.set_line(reader.lines.len());
+				}
+			}
+			Ok(None) if forward => reader.message = Some("This is the last chapter".to_string()),
+			Ok(None) => reader.message = Some("This is the first chapter".to_string()),
+			Err(err) => reader.message = Some(err),
+		}
+	}
+
+	Ok(())
+}
+
+/// What happens after the reader did the action of a key
+enum Step {
+	Stay,
+	Quit,
+	/// Opens the next or the previous chapter
+	Chapter(bool),
+	/// Shows the key bindings until a key is pressed
+	Help,
+}
+
+/// The wrapped lines of a chapter and the part of them which is on the screen
+struct Reader {
+	novel_title: String,
+	chapter: LightnovelChapter,
+	lines: Vec<Line>,
+	/// The column the reader starts at, the lists of the tui are left of it
+	origin: usize,
+	width: usize,
+	/// The columns of the text between the gutter and the right margin
+	text_width: usize,
+	/// The column the text starts at
+	left: usize,
+	/// The rows for the text, the last row of the terminal is the status line
+	height: usize,
+	/// The first line on the screen
+	top: usize,
+	/// The row of the cursor on the screen
+	cursor: usize,
+	/// Shown in the status line until the next key is pressed
+	message: Option<String>,
+	search: Option<Search>,
+	config: ReaderConfig,
+	themes: Vec<Theme>,
+	theme: usize,
+}
+
+impl Reader {
+	fn new(novel_title: String, chapter: LightnovelChapter, config: &ReaderConfig) -> Self {
+		let themes = config.get_themes();
+		let theme = themes.iter().position(|theme| theme.name == config.theme);
+
+		Self {
+			novel_title,
+			chapter,
+			lines: Vec::new(),
+			origin: 0,
+			width: 0,
+			text_width: 0,
+			left: TEXT_COL,
+			height: 1,
+			top: 0,
+			cursor: 0,
+			message: theme.is_none().then(|| format!("There is no theme called {}", config.theme)),
+			search: None,
+			config: config.clone(),
+			themes,
+			theme: theme.unwrap_or(0),
+		}
+	}
+
+	/// Shows the chapter from its start
+	fn set_chapter(&mut self, chapter: LightnovelChapter) {
+		self.chapter = chapter;
+		self.relayout();
+		self.top = 0;
+		self.cursor = 0;
+
+		if let Some(search) = &mut self.search {
+			search.find(&self.chapter, &self.lines);
+		}
+	}
+
+	/// Wraps the chapter again for the new size and keeps the cursor on the same text
+	fn resize(&mut self, width: usize, height: usize) {
+		let anchor = self.lines.get(self.current()).map(|line| (line.header, line.paragraph, line.range.start));
+
+		self.width = width;
+		self.height = height.saturating_sub(1).max(1);
+		self.relayout();
+		if let Some(search) = &mut self.search {
+			search.locate(&self.lines);
+		}
+
+		let line = match anchor {
+			Some((header, paragraph, offset)) => self
+				.lines
+				.iter()
+				.rposition(|line| line.header == header && line.paragraph == paragraph && line.range.start <= offset)
+				.unwrap_or(0),
+			None => 0,
+		};
+		self.set_line(line);
+	}
+
+	/// Wraps the chapter to the width of the terminal or the max width of the config, a narrower text is centred
+	fn relayout(&mut self) {
+		let available = self.width.saturating_sub(TEXT_COL + 1);
+		(self.text_width, self.left) = match self.config.max_width {
+			Some(max_width) => {
+				let text_width = min(max(max_width, 1), available);
+				(text_width, max(TEXT_COL, self.width.saturating_sub(text_width) / 2))
+			}
+			None => (available, TEXT_COL),
+		};
+
+		self.lines = layout(
+			self.chapter.get_title(),
+			&self.chapter,
+			self.text_width,
+			self.config.line_spacing,
+			self.config.paragraph_spacing,
+		);
+	}
+
+	/// The text the line shows
+	fn text(&self, line: &Line) -> &str {
+		if line.header {
+			&self.chapter.get_title()[line.range.clone()]
+		} else {
+			&self.chapter[line.paragraph][line.range.clone()]
+		}
+	}
+
+	/// If the action can be done right now, the actions which can't are skipped for the next action of the key
+	fn can(&self, action: Action) -> bool {
+		match action {
+			Action::NextMatch | Action::PrevMatch | Action::Cancel => self.search.is_some(),
+			_ => true,
+		}
+	}
+
+	/// Does the action of a key, the prompts are read on the last row of the terminal
+	fn act(&mut self, action: Option<Action>, term: &Term<()>, ln: &Lightnovel, bookmarks: &mut Bookmarks) -> Result<Step, Box<dyn Error>> {
+		let chapter = &self.chapter;
+		let paragraph = self.paragraph();
+		let backward = self.search.as_ref().map(|search| search.backward).unwrap_or(false);
+
+		let step = match action {
+			Some(Action::Quit) => Step::Quit,
+			Some(Action::Cancel) => {
+				self.search = None;
+				Step::Stay
+			}
+			Some(Action::Search) | Some(Action::SearchBackward) => {
+				let backward = action == Some(Action::SearchBackward);
+				let (mut regex, mut ignore_case) = self
+					.search
+					.as_ref()
+					.map(|search| (search.regex, search.ignore_case))
+					.unwrap_or((false, false));
+				let mut toggles = [
+					Toggle {
+						name: "regex",
+						key: Key::Ctrl('r'),
+						on: &mut regex,
+					},
+					Toggle {
+						name: "ignore case",
+						key: Key::Ctrl('t'),
+						on: &mut ignore_case,
+					},
+				];
+
+				if let Some(query) = prompt(term, self.status_line(), if backward { "?" } else { "/" }, &mut toggles) {
+					self.search(query, regex, ignore_case, backward);
+				}
+				Step::Stay
+			}
+			Some(Action::NextMatch) => {
+				self.jump_match(!backward, false);
+				Step::Stay
+			}
+			Some(Action::PrevMatch) => {
+				self.jump_match(backward, false);
+				Step::Stay
+			}
+			Some(Action::NextChapter) => Step::Chapter(true),
+			Some(Action::PrevChapter) => Step::Chapter(false),
+			Some(Action::Advance) if self.top == self.max_top() => Step::Chapter(true),
+			Some(Action::Retreat) if self.top == 0 => Step::Chapter(false),
+			Some(Action::LineUp) => {
+				self.move_cursor(-1);
+				Step::Stay
+			}
+			Some(Action::LineDown) => {
+				self.move_cursor(1);
+				Step::Stay
+			}
+			Some(Action::HalfPageUp) => {
+				self.scroll(-((self.height / 2).max(1) as isize));
+				Step::Stay
+			}
+			Some(Action::HalfPageDown) => {
+				self.scroll((self.height / 2).max(1) as isize);
+				Step::Stay
+			}
+			Some(Action::PageUp) | Some(Action::Retreat) => {
+				self.scroll(-(self.page() as isize));
+				Step::Stay
+			}
+			Some(Action::PageDown) | Some(Action::Advance) => {
+				self.scroll(self.page() as isize);
+				Step::Stay
+			}
+			Some(Action::Top) => {
+				self.set_line(0);
+				Step::Stay
+			}
+			Some(Action::Bottom) => {
+				self.set_line(self.lines.len());
+				Step::Stay
+			}
+			Some(Action::ToggleTheme) => {
+				self.theme = (self.theme + 1) % self.themes.len();
+				self.message = Some(format!("Theme: {}", self.themes[self.theme].name));
+				Step::Stay
+			}
+			Some(Action::OpenInBrowser) => {
+				if webbrowser::open(&absolute_url(chapter.get_url())).is_err() {
+					self.message = Some("Could not open the browser".to_string());
+				}
+				Step::Stay
+			}
+			Some(Action::Help) => Step::Help,
+			Some(Action::Bookmark) => {
+				if !bookmarks.remove(chapter.get_url(), paragraph, MarkKind::Bookmark) {
+					bookmarks.add(Bookmark::new(MarkKind::Bookmark, ln, chapter, paragraph, None));
+				}
+				bookmarks.save()?;
+				Step::Stay
+			}
+			Some(Action::Highlight) => {
+				match prompt(term, self.status_line(), "Note (empty removes the highlight): ", &mut []) {
+					Some(note) if note.is_empty() => {
+						bookmarks.remove(chapter.get_url(), paragraph, MarkKind::Highlight);
+					}
+					Some(note) => bookmarks.add(Bookmark::new(MarkKind::Highlight, ln, chapter, paragraph, Some(note))),
+					None => {}
+				}
+				bookmarks.save()?;
+				Step::Stay
+			}
+			None => Step::Stay,
+		};
+
+		Ok(step)
+	}
+
+	/// Marks the chapter as read once its end was on the screen
+	fn mark_read(&self, history: &mut History) -> Result<(), Box<dyn Error>> {
+		if self.progress() == 100 && history.mark_read(self.chapter.get_url()) {
+			history.save()?;
+		}
+		Ok(())
+	}
+
+	/// The index of the line under the cursor
+	fn current(&self) -> usize {
+		min(self.top + self.cursor, self.lines.len().saturating_sub(1))
+	}
+
+	/// The paragraph under the cursor
+	fn paragraph(&self) -> usize {
+		self.lines.get(self.current()).map(|line| line.paragraph).unwrap_or(0)
+	}
+
+	/// How many lines paging moves, one line of the last page stays on the screen
+	fn page(&self) -> usize {
+		self.height.saturating_sub(1).max(1)
+	}
+
+	/// How much of the chapter was read up to the bottom of the screen
+	fn progress(&self) -> usize {
+		match self.lines.len() {
+			0 => 100,
+			len => min(self.top + self.height, len) * 100 / len,
+		}
+	}
+
+	fn max_top(&self) -> usize {
+		self.lines.len().saturating_sub(self.height)
+	}
+
+	/// Puts the first line of the paragraph at the top of the screen
+	fn jump(&mut self, paragraph: usize) {
+		let line = self.lines.iter().position(|line| line.paragraph >= paragraph).unwrap_or(0);
+		self.top = min(line, self.max_top());
+		self.cursor = line - self.top;
+	}
+
+	/// Moves the cursor to the line and scrolls as little as possible to show it
+	fn set_line(&mut self, line: usize) {
+		let line = min(line, self.lines.len().saturating_sub(1));
+
+		if line < self.top {
+			self.top = line;
+		} else if line >= self.top + self.height {
+			self.top = line + 1 - self.height;
+		}
+		self.top = min(self.top, self.max_top());
+		self.cursor = line - self.top;
+	}
+
+	fn move_cursor(&mut self, delta: isize) {
+		self.set_line(self.current().saturating_add_signed(delta));
+	}
+
+	/// Scrolls the text and keeps the cursor on the same row, at the start or the end the cursor moves
+	/// to the first or last line instead
+	fn scroll(&mut self, delta: isize) {
+		let top = min(self.top.saturating_add_signed(delta), self.max_top());
+
+		if top == self.top {
+			self.set_line(if delta < 0 { 0 } else { self.lines.len() });
+		} else {
+			self.top = top;
+			self.cursor = min(self.cursor, self.lines.len().saturating_sub(self.top + 1));
+		}
+	}
+
+	/// Searches the chapter and moves to the first match from the cursor, an empty query repeats the last search
+	fn search(&mut self, query: String, regex: bool, ignore_case: bool, backward: bool) {
+		let query = match (query.is_empty(), &self.search) {
+			(false, _) => query,
+			(true, Some(search)) => search.query.clone(),
+			(true, None) => return,
+		};
+
+		match Search::new(query, regex, ignore_case, backward) {
+			Ok(mut search) => {
+				search.find(&self.chapter, &self.lines);
+				self.search = Some(search);
+				self.jump_match(!backward, true);
+			}
+			Err(err) => {
+				let reason = err
+					.to_string()
+					.lines()
+					.rfind(|line| !line.trim().is_empty())
+					.unwrap_or_default()
+					.to_string();
+				self.message = Some(format!("Invalid regex: {}", reason.trim_start_matches("error: ")));
+			}
+		}
+	}
+
+	/// Moves the cursor to the next match below or above it
+	fn jump_match(&mut self, forward: bool, inclusive: bool) {
+		let cursor = self.current();
+		let search = match &mut self.search {
+			Some(search) => search,
+			None => return,
+		};
+
+		let (index, wrapped) = match search.next(cursor, forward, inclusive) {
+			Some(next) => next,
+			None => {
+				self.message = Some(format!("Pattern not found: {}", search.query));
+				return;
+			}
+		};
+		search.selected = Some(index);
+
+		let line = search.matches[index].line;
+		self.message = Some(format!(
+			"Match {} of {}{}",
+			index + 1,
+			search.matches.len(),
+			if wrapped { ", search wrapped" } else { "" }
+		));
+		self.set_line(line);
+	}
+
+	fn draw(&self, term: &Term<()>, bookmarks: &Bookmarks) {
+		let _ = term.clear();
+		self.paint(term, bookmarks);
+
+		let _ = term.set_cursor(self.cursor, self.origin + self.left - TEXT_COL);
+		let _ = term.present();
+	}
+
+	/// Puts the text and the status line on the terminal without presenting them
+	fn paint(&self, term: &Term<()>, bookmarks: &Bookmarks) {
+		let url = self.chapter.get_url();
+		let theme = &self.themes[self.theme];
+
+		// Paint the background of the theme over the whole screen
+		for row in 0..self.height {
+			let _ = term.print_with_attr(row, self.origin, &" ".repeat(self.width), theme.text());
+		}
+
+		for (row, line) in self.lines.iter().skip(self.top).take(self.height).enumerate() {
+			let text = self.text(line);
+			let gaps = if self.config.justify && !line.last && !line.header {
+				justify(text, self.text_width)
+			} else {
+				Vec::new()
+			};
+
+			if line.first && !line.header && bookmarks.get(url, line.paragraph, MarkKind::Bookmark).is_some() {
+				let _ = term.print_with_attr(row, self.origin + self.left - TEXT_COL, "*", theme.mark());
+			}
+
+			let attr = if line.header {
+				theme.header()
+			} else if bookmarks.get(url, line.paragraph, MarkKind::Highlight).is_some() {
+				theme.highlight()
+			} else {
+				theme.text()
+			};
+			let _ = term.print_with_attr(row, self.origin + self.left, &stretch(text, &gaps), attr);
+
+			if line.header {
+				continue;
+			}
+
+			let matches = self.search.iter().flat_map(|search| search.matches.iter().enumerate());
+			for (i, m) in matches.filter(|(_, m)| m.paragraph == line.paragraph) {
+				let start = max(m.range.start, line.range.start);
+				let end = min(m.range.end, line.range.end);
+
+				let selected = self.search.as_ref().and_then(|search| search.selected) == Some(i);
+				let attr = if selected { theme.selected() } else { theme.matched() };
+
+				// Every character is put on its own column since the match can span stretched spaces
+				for (offset, c) in text
+					.char_indices()
+					.filter(|(offset, _)| (start..end).contains(&(line.range.start + offset)))
+				{
+					let _ = term.print_with_attr(row, self.origin + self.left + column(text, &gaps, offset), &c.to_string(), attr);
+				}
+			}
+		}
+
+		self.draw_status(term, bookmarks);
+	}
+
+	/// Shows the keys of the actions in a box in the middle of the screen
+	fn draw_help(&self, term: &Term<()>, bindings: &Bindings) {
+		let attr = self.themes[self.theme].status();
+
+		let mut rows = vec!["Key bindings".to_string(), String::new()];
+		rows.extend(Action::ALL.iter().filter_map(|action| {
+			let keys = bindings.keys(*action);
+			(!keys.is_empty()).then(|| format!("{:<24} {}", keys.join(", "), action.description()))
+		}));
+		rows.push(String::new());
+		rows.push("Press any key to close the help".to_string());
+
+		let width = min(rows.iter().map(|row| row.width()).max().unwrap_or_default() + 4, self.width);
+		let height = min(rows.len() + 2, self.height + 1);
+		let left = self.origin + self.width.saturating_sub(width) / 2;
+		let top = (self.height + 1).saturating_sub(height) / 2;
+
+		for row in 0..height {
+			let text = match row.checked_sub(1).and_then(|i| rows.get(i)) {
+				Some(text) => format!("  {}", text),
+				None => String::new(),
+			};
+			let _ = term.print_with_attr(top + row, left, &truncate(&text, width), attr);
+		}
+		let _ = term.present();
+	}
+
+	/// The row, column and width of the status line, the prompts are read there
+	fn status_line(&self) -> (usize, usize, usize) {
+		(self.height, self.origin, self.width)
+	}
+
+	/// Shows the message, the note of the highlight under the cursor or the titles, and the progress
+	fn draw_status(&self, term: &Term<()>, bookmarks: &Bookmarks) {
+		let theme = &self.themes[self.theme];
+		let status_attr = theme.status();
+
+		let progress = format!(" {}% ", self.progress());
+		let width = self.width.saturating_sub(progress.width());
+
+		let (text, attr) = match (
+			&self.message,
+			bookmarks.get(self.chapter.get_url(), self.paragraph(), MarkKind::Highlight),
+		) {
+			(Some(message), _) => (message.clone(), status_attr),
+			(None, Some(Bookmark { note: Some(note), .. })) => (note.clone(), theme.highlight()),
+			(None, _) => (format!("{} - {}", self.novel_title, self.chapter.get_title()), status_attr),
+		};
+
+		let _ = term.print_with_attr(self.height, self.origin, &truncate(&format!(" {}", text), width), attr);
+		let _ = term.print_with_attr(self.height, self.origin + width, &progress, status_attr);
+	}
+}
+
+/// Cuts the text to the display width and fills it up with spaces
+fn truncate(text: &str, width: usize) -> String {
+	let mut truncated = String::new();
+	let mut used = 0;
+
+	for c in text.chars() {
+		let char_width = c.width().unwrap_or(0);
+		if used + char_width > width {
+			break;
+		}
+		truncated.push(c);
+		used += char_width;
+	}
+
+	truncated + &" ".repeat(width - used)
+}
+
+/// A mode of the prompt which is switched on and off with its key
+struct Toggle<'a> {
+	name: &'static str,
+	key: Key,
+	on: &'a mut bool,
+}
+
+/// Reads a line on the row from the column on, the toggles which are on are shown before the label
+fn prompt(term: &Term<()>, (row, col, width): (usize, usize, usize), label: &str, toggles: &mut [Toggle]) -> Option<String> {
+	let mut input = String::new();
+
+	loop {
+		let modes = toggles.iter().filter(|toggle| *toggle.on).map(|toggle| toggle.name).collect::<Vec<_>>();
+		let text = if modes.is_empty() {
+			format!("{}{}", label, input)
+		} else {
+			format!("({}) {}{}", modes.join(", "), label, input)
+		};
+
+		let _ = term.print(row, col, &truncate(&text, width));
+		let _ = term.set_cursor(row, col + min(text.width(), width.saturating_sub(1)));
+		let _ = term.present();
+
+		match term.poll_event().ok()? {
+			Event::Key(Key::Enter) => return Some(input.trim().to_string()),
+			Event::Key(Key::ESC) => return None,
+			Event::Key(Key::Backspace) => {
+				input.pop();
+			}
+			Event::Key(Key::Char(c)) => input.push(c),
+			Event::Key(key) => {
+				for toggle in toggles.iter_mut().filter(|toggle| toggle.key == key) {
+					*toggle.on = !*toggle.on;
+				}
+			}
+			_ => {}
+		}
+	}
+}
@@ -0,0 +1,170 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use super::layout::Line;
+
+/// A search for text in the chapter of the reader
+pub struct Search {
+	pub query: String,
+	pub regex: bool,
+	pub ignore_case: bool,
+	/// If `n` goes up like after a search with `?`
+	pub backward: bool,
+	pattern: Regex,
+	pub matches: Vec<Match>,
+	/// The index of the match the cursor jumped to
+	pub selected: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+	pub paragraph: usize,
+	/// The bytes of the paragraph which match
+	pub range: Range<usize>,
+	/// The wrapped line the match starts on
+	pub line: usize,
+}
+
+impl Search {
+	/// Builds the search, the query is taken as it is unless it is a regex
+	pub fn new(query: String, regex: bool, ignore_case: bool, backward: bool) -> Result<Self, regex::Error> {
+		let pattern = if regex { query.clone() } else { regex::escape(&query) };
+		let pattern = RegexBuilder::new(&pattern).case_insensitive(ignore_case).build()?;
+
+		Ok(Self {
+			query,
+			regex,
+			ignore_case,
+			backward,
+			pattern,
+			matches: Vec::new(),
+			selected: None,
+		})
+	}
+
+	/// Finds the matches in the paragraphs and the lines they start on
+	pub fn find(&mut self, paragraphs: &[String], lines: &[Line]) {
+		self.matches = paragraphs
+			.iter()
+			.enumerate()
+			.flat_map(|(paragraph, text)| {
+				self.pattern.find_iter(text).filter(|m| !m.range().is_empty()).map(move |m| Match {
+					paragraph,
+					range: m.range(),
+					line: 0,
+				})
+			})
+			.collect();
+		self.selected = None;
+
+		self.locate(lines);
+	}
+
+	/// Looks up the lines of the matches again after the chapter was wrapped again
+	pub fn locate(&mut self, lines: &[Line]) {
+		for m in &mut self.matches {
+			// The lines of the chapter title come before the paragraphs
+			let position = (true, m.paragraph, m.range.start);
+			m.line = lines
+				.partition_point(|line| (!line.header, line.paragraph, line.range.start) <= position)
+				.saturating_sub(1);
+		}
+	}
+
+	/// The match after or before the selected match or the cursor line, it wraps around the chapter
+	///
+	/// With `inclusive` a match on the cursor line counts, it is used for a new search. Returns the index of the
+	/// match and if the search wrapped.
+	pub fn next(&self, cursor: usize, forward: bool, inclusive: bool) -> Option<(usize, bool)> {
+		if self.matches.is_empty() {
+			return None;
+		}
+
+		// The selected match only counts while the cursor is still on its line
+		let selected = self.selected.filter(|&i| self.matches.get(i).map(|m| m.line == cursor).unwrap_or(false));
+		// Matches are ordered by their line and then their index
+		let current = match selected {
+			Some(i) => (cursor, i),
+			None if forward != inclusive => (cursor, usize::MAX),
+			None => (cursor, 0),
+		};
+		let inclusive = inclusive && selected.is_none();
+
+		let mut positions = self.matches.iter().enumerate().map(|(i, m)| (m.line, i));
+		let found = if forward {
+			positions.position(|position| position > current || (inclusive && position == current))
+		} else {
+			positions.rposition(|position| position < current || (inclusive && position == current))
+		};
+
+		match found {
+			Some(i) => Some((i, false)),
+			None if forward => Some((0, true)),
+			None => Some((self.matches.len() - 1, true)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::reader::layout::layout;
+
+	/// A search with matches on the lines 2, 5, 5 and 9
+	fn search() -> Search {
+		let mut search = Search::new("x".to_string(), false, false, false).unwrap();
+		search.matches = [2, 5, 5, 9]
+			.iter()
+			.map(|&line| Match {
+				paragraph: line,
+				range: 0..1,
+				line,
+			})
+			.collect();
+		search
+	}
+
+	#[test]
+	fn next_goes_to_the_match_after_or_before_the_cursor() {
+		let search = search();
+		assert_eq!(search.next(0, true, false), Some((0, false)));
+		assert_eq!(search.next(5, true, false), Some((3, false)));
+		assert_eq!(search.next(5, false, false), Some((0, false)));
+		assert_eq!(search.next(5, true, true), Some((1, false)));
+	}
+
+	#[test]
+	fn next_wraps_around_the_chapter() {
+		let search = search();
+		assert_eq!(search.next(9, true, false), Some((0, true)));
+		assert_eq!(search.next(2, false, false), Some((3, true)));
+		assert_eq!(search.next(1, false, true), Some((3, true)));
+	}
+
+	#[test]
+	fn next_steps_through_the_matches_on_one_line() {
+		let mut search = search();
+		search.selected = Some(1);
+		assert_eq!(search.next(5, true, false), Some((2, false)));
+
+		search.selected = Some(2);
+		assert_eq!(search.next(5, false, false), Some((1, false)));
+
+		// The selection is forgotten once the cursor left its line
+		assert_eq!(search.next(6, false, false), Some((2, false)));
+	}
+
+	#[test]
+	fn locate_finds_the_line_of_a_match() {
+		let paragraphs = ["one two".to_string(), "three x".to_string()];
+		let lines = layout("Title", &paragraphs, 5, 0, 0);
+		let mut search = Search::new("X".to_string(), false, true, false).unwrap();
+		search.find(&paragraphs, &lines);
+
+		assert_eq!(search.matches.len(), 1);
+		assert_eq!(lines[search.matches[0].line].range, 6..7);
+		assert_eq!(search.next(0, true, true), Some((0, false)));
+		assert!(Search::new("(".to_string(), true, false, false).is_err());
+	}
+}
@@ -0,0 +1,230 @@
+use std::fmt::{self, Display, Formatter};
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tuikit::attr::{Attr, Color, Effect};
+
+use super::keys::{Actions, KeyPreset};
+
+/// The `[reader]` settings of the config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReaderConfig {
+	/// The name of the theme the reader starts with
+	pub theme: String,
+	/// Empty rows between the lines of a paragraph
+	pub line_spacing: usize,
+	/// Empty rows between paragraphs
+	pub paragraph_spacing: usize,
+	/// The widest the text gets, it is centred in wider terminals
+	pub max_width: Option<usize>,
+	/// Stretches the spaces of the lines so the text fills the whole width
+	pub justify: bool,
+	/// The key bindings the bindings below are added to, `default`, `vim` or `less`
+	pub keys: KeyPreset,
+	/// Binds keys like `"ctrl-n" = "next-chapter"` or `"n" = ["next-match", "next-chapter"]`
+	pub bindings: BTreeMap<String, Actions>,
+	/// More themes, a theme with the name of a built-in theme replaces it
+	pub themes: Vec<Theme>,
+}
+
+impl Default for ReaderConfig {
+	fn default() -> Self {
+		Self {
+			theme: "dark".to_string(),
+			line_spacing: 0,
+			paragraph_spacing: 1,
+			max_width: None,
+			justify: false,
+			keys: KeyPreset::default(),
+			bindings: BTreeMap::new(),
+			themes: Vec::new(),
+		}
+	}
+}
+
+impl ReaderConfig {
+	/// The built-in themes with the themes of the config
+	pub fn get_themes(&self) -> Vec<Theme> {
+		let mut themes = Theme::builtin();
+
+		for theme in &self.themes {
+			match themes.iter_mut().find(|t| t.name == theme.name) {
+				Some(builtin) => *builtin = theme.clone(),
+				None => themes.push(theme.clone()),
+			}
+		}
+
+		themes
+	}
+}
+
+/// The colours of the reader
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+	pub name: String,
+	pub foreground: Colour,
+	pub background: Colour,
+	/// The chapter title above the text
+	pub dim: Colour,
+	/// The star of bookmarked paragraphs
+	pub mark: Colour,
+	pub highlight_foreground: Colour,
+	pub highlight_background: Colour,
+	pub match_foreground: Colour,
+	pub match_background: Colour,
+	/// The match the cursor jumped to
+	pub selected_background: Colour,
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Self {
+			name: "dark".to_string(),
+			foreground: Colour(Color::AnsiValue(252)),
+			background: Colour(Color::Default),
+			dim: Colour(Color::AnsiValue(244)),
+			mark: Colour(Color::RED),
+			highlight_foreground: Colour(Color::BLACK),
+			highlight_background: Colour(Color::YELLOW),
+			match_foreground: Colour(Color::BLACK),
+			match_background: Colour(Color::CYAN),
+			selected_background: Colour(Color::GREEN),
+		}
+	}
+}
+
+impl Theme {
+	pub fn builtin() -> Vec<Theme> {
+		vec![
+			Theme::default(),
+			Theme {
+				name: "light".to_string(),
+				foreground: Colour(Color::AnsiValue(236)),
+				background: Colour(Color::AnsiValue(255)),
+				dim: Colour(Color::AnsiValue(245)),
+				mark: Colour(Color::AnsiValue(160)),
+				highlight_foreground: Colour(Color::AnsiValue(236)),
+				highlight_background: Colour(Color::AnsiValue(229)),
+				match_foreground: Colour(Color::AnsiValue(236)),
+				match_background: Colour(Color::AnsiValue(153)),
+				selected_background: Colour(Color::AnsiValue(114)),
+			},
+			Theme {
+				name: "sepia".to_string(),
+				foreground: Colour(Color::Rgb(0x5b, 0x46, 0x36)),
+				background: Colour(Color::Rgb(0xf4, 0xec, 0xd8)),
+				dim: Colour(Color::Rgb(0xa0, 0x8c, 0x74)),
+				mark: Colour(Color::Rgb(0xa3, 0x3b, 0x20)),
+				highlight_foreground: Colour(Color::Rgb(0x5b, 0x46, 0x36)),
+				highlight_background: Colour(Color::Rgb(0xe8, 0xd4, 0x8b)),
+				match_foreground: Colour(Color::Rgb(0x5b, 0x46, 0x36)),
+				match_background: Colour(Color::Rgb(0xd6, 0xc3, 0xa0)),
+				selected_background: Colour(Color::Rgb(0xc9, 0xa6, 0x6b)),
+			},
+			Theme {
+				name: "high-contrast".to_string(),
+				foreground: Colour(Color::LIGHT_WHITE),
+				background: Colour(Color::BLACK),
+				dim: Colour(Color::AnsiValue(250)),
+				mark: Colour(Color::LIGHT_RED),
+				highlight_foreground: Colour(Color::BLACK),
+				highlight_background: Colour(Color::LIGHT_YELLOW),
+				match_foreground: Colour(Color::BLACK),
+				match_background: Colour(Color::LIGHT_CYAN),
+				selected_background: Colour(Color::LIGHT_GREEN),
+			},
+		]
+	}
+
+	pub fn text(&self) -> Attr {
+		Attr::default().fg(self.foreground.0).bg(self.background.0)
+	}
+
+	pub fn header(&self) -> Attr {
+		Attr::default().fg(self.dim.0).bg(self.background.0)
+	}
+
+	pub fn mark(&self) -> Attr {
+		Attr::default().fg(self.mark.0).bg(self.background.0)
+	}
+
+	pub fn highlight(&self) -> Attr {
+		Attr::default().fg(self.highlight_foreground.0).bg(self.highlight_background.0)
+	}
+
+	pub fn matched(&self) -> Attr {
+		Attr::default().fg(self.match_foreground.0).bg(self.match_background.0)
+	}
+
+	pub fn selected(&self) -> Attr {
+		self.matched().bg(self.selected_background.0).effect(Effect::BOLD)
+	}
+
+	pub fn status(&self) -> Attr {
+		self.text().effect(Effect::REVERSE)
+	}
+}
+
+/// A colour of a theme, it is written like `default`, `red`, `bright-red`, `208` or `#f4ecd8`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Colour(pub Color);
+
+const NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+impl TryFrom<String> for Colour {
+	type Error = String;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		let name = value.trim().to_lowercase();
+
+		if name == "default" {
+			return Ok(Self(Color::Default));
+		}
+		if let Some(i) = NAMES.iter().position(|n| *n == name) {
+			return Ok(Self(Color::AnsiValue(i as u8)));
+		}
+		if let Some(i) = name.strip_prefix("bright-").and_then(|n| NAMES.iter().position(|m| *m == n)) {
+			return Ok(Self(Color::AnsiValue(i as u8 + 8)));
+		}
+		if let Ok(value) = name.parse::<u8>() {
+			return Ok(Self(Color::AnsiValue(value)));
+		}
+
+		let hex = name.strip_prefix('#').unwrap_or_default();
+		let channels = match hex.len() {
+			3 if hex.is_ascii() => hex
+				.chars()
+				.map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16))
+				.collect::<Result<Vec<_>, _>>(),
+			6 if hex.is_ascii() => (0..6).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16)).collect(),
+			_ => return Err(format!("{} is not a colour, use a name, a number up to 255 or #rrggbb", value)),
+		};
+
+		match channels {
+			Ok(rgb) => Ok(Self(Color::Rgb(rgb[0], rgb[1], rgb[2]))),
+			Err(_) => Err(format!("{} is not a colour, use a name, a number up to 255 or #rrggbb", value)),
+		}
+	}
+}
+
+impl Display for Colour {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self.0 {
+			Color::AnsiValue(i) if i < 8 => f.write_str(NAMES[i as usize]),
+			Color::AnsiValue(i) if i < 16 => write!(f, "bright-{}", NAMES[i as usize - 8]),
+			Color::AnsiValue(i) => write!(f, "{}", i),
+			Color::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+			_ => f.write_str("default"),
+		}
+	}
+}
+
+impl From<Colour> for String {
+	fn from(colour: Colour) -> Self {
+		colour.to_string()
+	}
+}
@@ -0,0 +1,751 @@
+use std::cmp::min;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ln_lib::{Lightnovel, LightnovelCategory, LightnovelChapter, LightnovelList, Store};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::JoinHandle;
+use tuikit::attr::{Attr, Effect};
+use tuikit::error::TuikitError;
+use tuikit::event::{Event, Key};
+use tuikit::term::{Term, TermHeight, TermOptions};
+
+use super::chapters::Chapters;
+use super::keys::{Action, Bindings};
+use super::{prompt, truncate, Reader, ReaderConfig, Step, TEXT_COL, WHEEL_LINES};
+use crate::bookmark::Bookmarks;
+use crate::history::History;
+
+/// The widest the list of lightnovels gets, it takes a quarter of the terminal on narrow terminals
+const NOVELS_WIDTH: usize = 32;
+/// The widest the list of chapters gets
+const CHAPTERS_WIDTH: usize = 40;
+/// How often the loading indicator turns while something loads
+const TICK: Duration = Duration::from_millis(100);
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// The pane the keys go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+	Novels,
+	Chapters,
+	Reader,
+}
+
+/// What a task in the background loaded, the id tells if the task is still wanted
+enum Loaded {
+	Results {
+		id: usize,
+		novels: Result<Vec<Lightnovel>, String>,
+	},
+	/// The chapters of a page of the chapter index
+	Index {
+		id: usize,
+		chapters: Vec<LightnovelChapter>,
+		page: usize,
+		pages: usize,
+	},
+	/// The last page of the chapter index was loaded or a page failed
+	IndexDone {
+		id: usize,
+		err: Option<String>,
+	},
+	/// The chapter with the lightnovel it is from, it is `None` after the last or before the first chapter
+	Chapter {
+		id: usize,
+		ln: Box<Lightnovel>,
+		chapter: Result<Option<LightnovelChapter>, String>,
+		at_end: bool,
+	},
+}
+
+/// Sends what a task loaded to the tui and wakes it up
+#[derive(Clone)]
+struct Waker {
+	tx: UnboundedSender<Loaded>,
+	term: Arc<Term<()>>,
+}
+
+impl Waker {
+	fn send(&self, loaded: Loaded) {
+		if self.tx.send(loaded).is_ok() {
+			let _ = self.term.send_event(Event::User(()));
+		}
+	}
+}
+
+/// A task which loads something in the background, it is aborted once it isn't wanted anymore
+struct Task {
+	id: usize,
+	/// What is loaded, it is shown next to the loading indicator
+	what: String,
+	handle: JoinHandle<()>,
+}
+
+impl Drop for Task {
+	fn drop(&mut self) {
+		self.handle.abort();
+	}
+}
+
+/// The selected item of a list and the first row of the list on the screen
+#[derive(Debug, Default)]
+struct Cursor {
+	selected: usize,
+	top: usize,
+}
+
+impl Cursor {
+	fn move_by(&mut self, delta: isize, len: usize) {
+		self.selected = min(self.selected.saturating_add_signed(delta), len.saturating_sub(1));
+	}
+
+	/// Scrolls as little as possible to show the row of the selected item, a list without rows still shows one
+	fn fit(&mut self, row: usize, height: usize) {
+		let height = height.max(1);
+		if row < self.top {
+			self.top = row;
+		} else if row >= self.top + height {
+			self.top = row + 1 - height;
+		}
+	}
+}
+
+/// The lists of lightnovels and chapters and the reader on one screen
+struct App {
+	config: ReaderConfig,
+	bindings: Bindings,
+	waker: Waker,
+	width: usize,
+	height: usize,
+	focus: Focus,
+	/// The lists are hidden and the reader takes the whole width
+	zoom: bool,
+	library: Vec<Lightnovel>,
+	/// The query of the last search and the lightnovels it found, they are listed below the library
+	search: Option<(String, Result<Vec<Lightnovel>, String>)>,
+	novels: Cursor,
+	/// The lightnovel in the list of chapters
+	ln: Option<Lightnovel>,
+	chapters: Vec<LightnovelChapter>,
+	chapter_cursor: Cursor,
+	/// How much of the chapter index is loaded or why it failed
+	index_status: String,
+	/// The lightnovel of the chapter in the reader
+	reading: Lightnovel,
+	reader: Option<Reader>,
+	/// Shown in the last row until the next key is pressed
+	message: Option<String>,
+	searching: Option<Task>,
+	indexing: Option<Task>,
+	opening: Option<Task>,
+	next_id: usize,
+	tick: usize,
+}
+
+/// Shows the downloaded lightnovels, the search results, their chapters and the reader until the user quits
+///
+/// Everything is loaded in tasks, so the keys work while the lists and chapters load.
+pub async fn tui(store: &Store, bookmarks: &mut Bookmarks, history: &mut History, config: &ReaderConfig) -> Result<(), Box<dyn Error>> {
+	let bindings = Bindings::new(config.keys, &config.bindings)?;
+	let library = store.lightnovels()?;
+
+	let term = Arc::new(Term::with_options(TermOptions::default().height(TermHeight::Percent(100)).mouse_enabled(true)).unwrap());
+	let (tx, mut rx) = unbounded_channel();
+
+	let mut app = App {
+		config: config.clone(),
+		bindings,
+		waker: Waker { tx, term: term.clone() },
+		width: 0,
+		height: 0,
+		focus: Focus::Novels,
+		zoom: false,
+		library,
+		search: None,
+		novels: Cursor::default(),
+		ln: None,
+		chapters: Vec::new(),
+		chapter_cursor: Cursor::default(),
+		index_status: String::new(),
+		reading: Lightnovel::default(),
+		reader: None,
+		message: None,
+		searching: None,
+		indexing: None,
+		opening: None,
+		next_id: 0,
+		tick: 0,
+	};
+	let (width, height) = term.term_size().unwrap_or((80, 24));
+	app.resize(width, height);
+
+	loop {
+		while let Ok(loaded) = rx.try_recv() {
+			app.receive(loaded, bookmarks)?;
+		}
+		if let Some(reader) = &app.reader {
+			reader.mark_read(history)?;
+		}
+		app.draw(&term, bookmarks, history);
+
+		// The loading indicator turns while something loads, otherwise the loop waits for the next event
+		let ev = if app.is_loading() { term.peek_event(TICK) } else { term.poll_event() };
+		let ev = match ev {
+			Ok(ev) => ev,
+			Err(TuikitError::Timeout(_)) => {
+				app.tick += 1;
+				continue;
+			}
+			Err(_) => break,
+		};
+
+		let key = match ev {
+			Event::Key(Key::WheelUp(_, _, count)) => {
+				app.scroll(-((count as usize * WHEEL_LINES) as isize));
+				continue;
+			}
+			Event::Key(Key::WheelDown(_, _, count)) => {
+				app.scroll((count as usize * WHEEL_LINES) as isize);
+				continue;
+			}
+			Event::Resize { width, height } => {
+				app.resize(width, height);
+				continue;
+			}
+			Event::Key(key) => key,
+			_ => continue,
+		};
+		app.message = None;
+		if let Some(reader) = &mut app.reader {
+			reader.message = None;
+		}
+
+		match key {
+			Key::Ctrl('c') => break,
+			Key::Tab => app.cycle(true),
+			Key::BackTab => app.cycle(false),
+			Key::Ctrl('w') if app.reader.is_some() => {
+				app.zoom = !app.zoom;
+				app.focus = Focus::Reader;
+				app.layout();
+			}
+			_ if app.focus == Focus::Reader => {
+				if !app.read(key, &term, bookmarks, history)? {
+					app.focus = Focus::Chapters;
+					app.zoom = false;
+					app.layout();
+				}
+			}
+			Key::Char('q') => break,
+			_ => app.list_key(key, &term),
+		}
+	}
+
+	Ok(())
+}
+
+impl App {
+	fn resize(&mut self, width: usize, height: usize) {
+		self.width = width;
+		self.height = height;
+		self.layout();
+	}
+
+	/// The widths of the list of lightnovels and the list of chapters, they are 0 while the reader is zoomed
+	fn columns(&self) -> (usize, usize) {
+		if self.zoom {
+			(0, 0)
+		} else {
+			(min(NOVELS_WIDTH, self.width / 4), min(CHAPTERS_WIDTH, self.width / 4))
+		}
+	}
+
+	/// The column the reader starts at, the lists are left of it with a separator after each of them
+	fn reader_origin(&self) -> usize {
+		match self.columns() {
+			(0, 0) => 0,
+			(novels, chapters) => novels + chapters + 2,
+		}
+	}
+
+	/// The rows of the panes, the last row of the terminal shows the keys, messages and what is loading
+	fn pane_height(&self) -> usize {
+		self.height.saturating_sub(1).max(1)
+	}
+
+	/// Puts the reader right of the lists
+	fn layout(&mut self) {
+		let origin = self.reader_origin();
+		let (width, height) = (self.width.saturating_sub(origin), self.pane_height());
+
+		if let Some(reader) = &mut self.reader {
+			reader.origin = origin;
+			reader.resize(width, height);
+		}
+	}
+
+	fn is_loading(&self) -> bool {
+		self.searching.is_some() || self.indexing.is_some() || self.opening.is_some()
+	}
+
+	fn spawn<F>(&mut self, what: String, task: impl FnOnce(usize, Waker) -> F) -> Task
+	where
+		F: std::future::Future<Output = ()> + Send + 'static,
+	{
+		self.next_id += 1;
+
+		Task {
+			id: self.next_id,
+			what,
+			handle: tokio::spawn(task(self.next_id, self.waker.clone())),
+		}
+	}
+
+	/// The lightnovel at the index of the list, the library comes before the search results
+	fn novel(&self, index: usize) -> Option<&Lightnovel> {
+		let results = match &self.search {
+			Some((_, Ok(results))) => results.as_slice(),
+			_ => &[],
+		};
+
+		self.library.get(index).or_else(|| results.get(index - self.library.len()))
+	}
+
+	fn novel_count(&self) -> usize {
+		match &self.search {
+			Some((_, Ok(results))) => self.library.len() + results.len(),
+			_ => self.library.len(),
+		}
+	}
+
+	/// The row of the lightnovel in the list, below the header of the library or of the search
+	fn novel_row(&self, index: usize) -> usize {
+		if index < self.library.len() {
+			index + 1
+		} else {
+			index + 2
+		}
+	}
+
+	/// Takes what a task loaded if the task is still wanted
+	fn receive(&mut self, loaded: Loaded, bookmarks: &mut Bookmarks) -> Result<(), Box<dyn Error>> {
+		let wanted = |task: &Option<Task>, id: usize| task.as_ref().map(|task| task.id) == Some(id);
+
+		match loaded {
+			Loaded::Results { id, novels } if wanted(&self.searching, id) => {
+				if let Err(err) = &novels {
+					self.message = Some(format!("The search failed: {}", err));
+				}
+				if let Some((_, results)) = &mut self.search {
+					*results = novels;
+				}
+				self.searching = None;
+			}
+			Loaded::Index {
+				id,
+				mut chapters,
+				page,
+				pages,
+			} if wanted(&self.indexing, id) => {
+				self.chapters.append(&mut chapters);
+				self.index_status = format!("page {}/{}", page, pages);
+			}
+			Loaded::IndexDone { id, err } if wanted(&self.indexing, id) => {
+				self.index_status = match err {
+					Some(err) => err,
+					None => format!("{} chapters", self.chapters.len()),
+				};
+				self.indexing = None;
+			}
+			Loaded::Chapter { id, ln, chapter, at_end } if wanted(&self.opening, id) => {
+				self.opening = None;
+
+				match chapter {
+					Ok(Some(chapter)) => {
+						if bookmarks.reanchor(&chapter) {
+							bookmarks.save()?;
+						}
+						self.show(*ln, chapter, at_end);
+					}
+					Ok(None) if at_end => self.message = Some("This is the first chapter".to_string()),
+					Ok(None) => self.message = Some("This is the last chapter".to_string()),
+					Err(err) => self.message = Some(err),
+				}
+			}
+			// A task which was replaced sent it before it was aborted
+			_ => {}
+		}
+
+		Ok(())
+	}
+
+	/// Shows the chapter in the reader, at its end if the reader went back to it
+	fn show(&mut self, ln: Lightnovel, chapter: LightnovelChapter, at_end: bool) {
+		match &mut self.reader {
+			Some(reader) => {
+				reader.novel_title = ln.get_title().clone();
+				reader.set_chapter(chapter);
+			}
+			None => self.reader = Some(Reader::new(ln.get_title().clone(), chapter, &self.config)),
+		}
+		self.reading = ln;
+		self.layout();
+
+		if let Some(reader) = self.reader.as_mut().filter(|_| at_end) {
+			reader.set_line(reader.lines.len());
+		}
+	}
+
+	/// Lists the lightnovels with the title like the query below the library
+	fn search(&mut self, query: String) {
+		self.search = Some((query.clone(), Ok(Vec::new())));
+
+		let what = format!("Searching for {}", query);
+		self.searching = Some(self.spawn(what, |id, waker| async move {
+			let novels = match LightnovelList::new(LightnovelCategory::Title(query)) {
+				Ok(mut list) => list.scrape().await.map(|_| list.to_vec()),
+				Err(err) => Err(err),
+			};
+			waker.send(Loaded::Results {
+				id,
+				novels: novels.map_err(|err| err.to_string()),
+			});
+		}));
+	}
+
+	/// Lists the chapters of the lightnovel, the pages of the chapter index are added while they load
+	fn open_novel(&mut self, ln: Lightnovel) {
+		self.chapters.clear();
+		self.chapter_cursor = Cursor::default();
+		self.index_status = "loading".to_string();
+		self.ln = Some(ln.clone());
+
+		let what = format!("Loading the chapters of {}", ln.get_title());
+		self.indexing = Some(self.spawn(what, |id, waker| async move {
+			let mut ln = ln;
+			let err = load_index(&mut ln, id, &waker).await.err();
+			waker.send(Loaded::IndexDone { id, err });
+		}));
+	}
+
+	/// Loads the chapter of the list of chapters into the reader
+	fn open_chapter(&mut self, chapter: LightnovelChapter) {
+		let ln = match &self.ln {
+			Some(ln) => ln.clone(),
+			None => return,
+		};
+
+		let what = format!("Loading {}", chapter.get_title());
+		self.opening = Some(self.spawn(what, |id, waker| async move {
+			let mut chapter = chapter;
+			let chapter = match chapter.scrape().await {
+				Ok(_) => Ok(Some(chapter)),
+				Err(err) => Err(err.to_string()),
+			};
+			waker.send(Loaded::Chapter {
+				id,
+				ln: Box::new(ln),
+				chapter,
+				at_end: false,
+			});
+		}));
+	}
+
+	/// Loads the chapter after or before the chapter in the reader
+	fn open_adjacent(&mut self, forward: bool) {
+		let number = match &self.reader {
+			Some(reader) => reader.chapter.get_chapter_number(),
+			None => return,
+		};
+		let mut chapters = Chapters::new(self.reading.clone());
+
+		let what = if forward {
+			"Loading the next chapter"
+		} else {
+			"Loading the previous chapter"
+		};
+		self.opening = Some(self.spawn(what.to_string(), |id, waker| async move {
+			let chapter = chapters.load(number, forward).await;
+			waker.send(Loaded::Chapter {
+				id,
+				ln: Box::new(chapters.get_lightnovel().clone()),
+				chapter,
+				at_end: !forward,
+			});
+		}));
+	}
+
+	/// Moves the focus to the next or previous pane, the reader only has the focus once a chapter was opened
+	fn cycle(&mut self, forward: bool) {
+		let panes = if self.reader.is_some() {
+			vec![Focus::Novels, Focus::Chapters, Focus::Reader]
+		} else {
+			vec![Focus::Novels, Focus::Chapters]
+		};
+		let current = panes.iter().position(|pane| *pane == self.focus).unwrap_or(0);
+
+		self.focus = if forward {
+			panes[(current + 1) % panes.len()]
+		} else {
+			panes[(current + panes.len() - 1) % panes.len()]
+		};
+		if self.zoom && self.focus != Focus::Reader {
+			self.zoom = false;
+			self.layout();
+		}
+	}
+
+	/// Moves the selection of the list with the focus or scrolls the reader
+	fn scroll(&mut self, delta: isize) {
+		match self.focus {
+			Focus::Novels => self.novels.move_by(delta, self.novel_count()),
+			Focus::Chapters => self.chapter_cursor.move_by(delta, self.chapters.len()),
+			Focus::Reader => {
+				if let Some(reader) = &mut self.reader {
+					reader.scroll(delta);
+				}
+			}
+		}
+	}
+
+	/// Does the action of the key in the reader, returns false once the reader is left
+	fn read(&mut self, key: Key, term: &Term<()>, bookmarks: &mut Bookmarks, history: &mut History) -> Result<bool, Box<dyn Error>> {
+		let reader = match &mut self.reader {
+			Some(reader) => reader,
+			None => return Ok(false),
+		};
+
+		// The first action of the key which can be done right now
+		let action = self.bindings.get(&key).iter().copied().find(|action| reader.can(*action));
+
+		match reader.act(action, term, &self.reading, bookmarks)? {
+			Step::Stay => {}
+			Step::Quit => return Ok(false),
+			Step::Chapter(forward) => self.open_adjacent(forward),
+			Step::Help => self.help(term, bookmarks, history),
+		}
+
+		Ok(true)
+	}
+
+	/// Shows the key bindings of the reader until a key is pressed
+	fn help(&mut self, term: &Term<()>, bookmarks: &Bookmarks, history: &History) {
+		loop {
+			self.draw(term, bookmarks, history);
+			if let Some(reader) = &self.reader {
+				reader.draw_help(term, &self.bindings);
+			}
+
+			match term.poll_event() {
+				Ok(Event::Resize { width, height }) => self.resize(width, height),
+				Ok(Event::Key(_)) | Err(_) => return,
+				_ => {}
+			}
+		}
+	}
+
+	/// Moves in the lists, opens the selected lightnovel or chapter and searches
+	fn list_key(&mut self, key: Key, term: &Term<()>) {
+		let page = self.pane_height().saturating_sub(1).max(1) as isize;
+
+		match key {
+			Key::Up | Key::Char('k') => self.scroll(-1),
+			Key::Down | Key::Char('j') => self.scroll(1),
+			Key::PageUp => self.scroll(-page),
+			Key::PageDown => self.scroll(page),
+			Key::Home | Key::Char('g') => self.scroll(isize::MIN),
+			Key::End | Key::Char('G') => self.scroll(isize::MAX),
+			Key::Left | Key::Char('h') | Key::ESC if self.focus == Focus::Chapters => self.focus = Focus::Novels,
+			Key::Char('/') => {
+				if let Some(query) = prompt(term, (self.height.saturating_sub(1), 0, self.width), "Search: ", &mut []).filter(|query| !query.is_empty()) {
+					self.search(query);
+					self.focus = Focus::Novels;
+					self.novels.selected = self.library.len();
+				}
+			}
+			Key::Enter | Key::Right | Key::Char('l') => match self.focus {
+				Focus::Novels => {
+					if let Some(ln) = self.novel(self.novels.selected).cloned() {
+						self.open_novel(ln);
+						self.focus = Focus::Chapters;
+					}
+				}
+				Focus::Chapters => {
+					if let Some(chapter) = self.chapters.get(self.chapter_cursor.selected).cloned() {
+						self.open_chapter(chapter);
+						self.focus = Focus::Reader;
+					}
+				}
+				Focus::Reader => {}
+			},
+			_ => {}
+		}
+	}
+
+	fn draw(&mut self, term: &Term<()>, bookmarks: &Bookmarks, history: &History) {
+		let _ = term.clear();
+
+		let height = self.pane_height().saturating_sub(1);
+		self.novels.fit(self.novel_row(self.novels.selected), height);
+		self.chapter_cursor.fit(self.chapter_cursor.selected, height);
+
+		let (novels_width, chapters_width) = self.columns();
+		if novels_width > 0 {
+			// The empty rows are painted too, otherwise the text of the zoomed reader stays there
+			for row in 0..self.pane_height() {
+				let _ = term.print(row, 0, &format!("{:novels_width$}│{:chapters_width$}│", "", ""));
+			}
+			self.draw_novels(term, novels_width);
+			self.draw_chapters(term, novels_width + 1, chapters_width, history);
+		}
+
+		match &self.reader {
+			Some(reader) => reader.paint(term, bookmarks),
+			None => {
+				let text = match &self.opening {
+					Some(task) => format!("{}...", task.what),
+					None => "Pick a lightnovel and a chapter to read".to_string(),
+				};
+				let _ = term.print(self.pane_height() / 2, self.reader_origin() + TEXT_COL, &text);
+			}
+		}
+
+		self.draw_status(term);
+
+		let cursor = match (self.focus, &self.reader) {
+			(Focus::Novels, _) => ((self.novel_row(self.novels.selected) + 1).saturating_sub(self.novels.top), 0),
+			(Focus::Chapters, _) => (
+				(self.chapter_cursor.selected + 1).saturating_sub(self.chapter_cursor.top),
+				novels_width + 1,
+			),
+			(Focus::Reader, Some(reader)) => (reader.cursor, reader.origin + reader.left - TEXT_COL),
+			(Focus::Reader, None) => (0, self.reader_origin()),
+		};
+		let _ = term.set_cursor(cursor.0, cursor.1);
+		let _ = term.present();
+	}
+
+	/// Draws the library and the search results with a header above each of them
+	fn draw_novels(&self, term: &Term<()>, width: usize) {
+		let mut rows = vec![(format!("Library ({})", self.library.len()), true)];
+		rows.extend(self.library.iter().map(|ln| (ln.get_title().clone(), false)));
+
+		match &self.search {
+			Some((query, _)) if self.searching.is_some() => rows.push((format!("Search: {} (searching)", query), true)),
+			Some((query, Ok(results))) => {
+				rows.push((format!("Search: {} ({})", query, results.len()), true));
+				rows.extend(results.iter().map(|ln| (ln.get_title().clone(), false)));
+			}
+			Some((query, Err(_))) => rows.push((format!("Search: {} (failed)", query), true)),
+			None => {}
+		}
+
+		let selected = self.novel_row(self.novels.selected);
+		let attr = self.list_attr(Focus::Novels);
+		let _ = term.print_with_attr(0, 0, &truncate(" Lightnovels", width), attr);
+
+		for (row, (text, header)) in rows.iter().enumerate().skip(self.novels.top).take(self.pane_height() - 1) {
+			let attr = if row == selected && !*header {
+				attr
+			} else if *header {
+				Attr::default().effect(Effect::BOLD)
+			} else {
+				Attr::default()
+			};
+			let text = if *header { format!(" {}", text) } else { format!("  {}", text) };
+
+			let _ = term.print_with_attr(row - self.novels.top + 1, 0, &truncate(&text, width), attr);
+		}
+	}
+
+	/// Draws the chapters with a mark on the chapters which were read
+	fn draw_chapters(&self, term: &Term<()>, col: usize, width: usize, history: &History) {
+		let title = match &self.ln {
+			Some(ln) => format!(" {} ({})", ln.get_title(), self.index_status),
+			None => " Chapters".to_string(),
+		};
+		let attr = self.list_attr(Focus::Chapters);
+		let _ = term.print_with_attr(0, col, &truncate(&title, width), attr);
+
+		let open = self.reader.as_ref().map(|reader| reader.chapter.get_url());
+		for (row, chapter) in self
+			.chapters
+			.iter()
+			.enumerate()
+			.skip(self.chapter_cursor.top)
+			.take(self.pane_height() - 1)
+		{
+			let mark = if history.is_read(chapter.get_url()) { '✓' } else { '·' };
+			let text = format!("{} {:>4} {}", mark, chapter.get_chapter_number(), chapter.get_title());
+
+			let attr = if row == self.chapter_cursor.selected {
+				attr
+			} else if open == Some(chapter.get_url()) {
+				Attr::default().effect(Effect::BOLD)
+			} else {
+				Attr::default()
+			};
+			let _ = term.print_with_attr(row - self.chapter_cursor.top + 1, col, &truncate(&text, width), attr);
+		}
+	}
+
+	/// The title and the selection of a list are reversed while it has the focus
+	fn list_attr(&self, pane: Focus) -> Attr {
+		if self.focus == pane {
+			Attr::default().effect(Effect::REVERSE)
+		} else {
+			Attr::default().effect(Effect::UNDERLINE)
+		}
+	}
+
+	/// Shows what is loading, the message or the keys of the pane with the focus
+	fn draw_status(&self, term: &Term<()>) {
+		let loading = [&self.searching, &self.indexing, &self.opening]
+			.into_iter()
+			.flatten()
+			.map(|task| task.what.as_str())
+			.collect::<Vec<_>>();
+
+		let text = if let Some(message) = &self.message {
+			message.clone()
+		} else if !loading.is_empty() {
+			format!("{} {}", SPINNER[self.tick % SPINNER.len()], loading.join(", "))
+		} else {
+			match self.focus {
+				Focus::Novels => "enter: chapters  /: search  tab: next pane  q: quit".to_string(),
+				Focus::Chapters => "enter: read  esc: lightnovels  /: search  tab: next pane  q: quit".to_string(),
+				Focus::Reader => format!(
+					"{}: help  ctrl-w: {} the lists  tab: next pane  ctrl-c: quit",
+					self.bindings.keys(Action::Help).first().map(String::as_str).unwrap_or("f1"),
+					if self.zoom { "show" } else { "hide" }
+				),
+			}
+		};
+
+		let _ = term.print(self.height.saturating_sub(1), 0, &truncate(&format!(" {}", text), self.width));
+	}
+}
+
+/// Sends the chapters of every page of the chapter index, it stops at the first page which fails
+async fn load_index(ln: &mut Lightnovel, id: usize, waker: &Waker) -> Result<(), String> {
+	ln.scrape().await.map_err(|err| err.to_string())?;
+	let pages = ln.get_last_page().unwrap_or(1);
+
+	loop {
+		waker.send(Loaded::Index {
+			id,
+			chapters: ln.to_vec(),
+			page: ln.get_page(),
+			pages,
+		});
+
+		if ln.next_page().is_none() {
+			return Ok(());
+		}
+		if let Err(err) = ln.scrape().await {
+			return Err(format!("page {}/{} failed: {}", ln.get_page(), pages, err));
+		}
+	}
+}
@@ -0,0 +1,121 @@
+use std::error::Error;
+use std::io::{self, IsTerminal};
+
+use clap::Args;
+use ln_lib::{Lightnovel, LightnovelCategory, LightnovelChapter, LightnovelList};
+
+use crate::menu::{show_ln, PickerConfig};
+use crate::output::OutputFormat;
+use crate::{Failure, AMBIGUOUS, NOT_FOUND};
+
+#[derive(Args)]
+pub struct NovelArgs {
+	/// the title or the url of the lightnovel
+	novel: String,
+
+	/// take the lightnovel at this position of the search results instead of picking one, counted over all pages like `search --pages` numbers them
+	#[clap(long)]
+	index: Option<usize>,
+}
+
+impl NovelArgs {
+	/// Finds the lightnovel by its url, its position in the search results or its title
+	///
+	/// If the title matches several lightnovels the user picks one in the picker when it is set,
+	/// otherwise it fails. Returns `None` if the user didn't pick one.
+	pub async fn find(&self, picker: Option<&PickerConfig>) -> Result<Option<Lightnovel>, Box<dyn Error>> {
+		if is_url(&self.novel) {
+			let mut ln = Lightnovel::new(String::new(), self.novel.clone());
+			ln.scrape().await?;
+			return Ok(Some(ln));
+		}
+
+		let mut list = LightnovelList::new(LightnovelCategory::Title(self.novel.clone()))?;
+		list.scrape().await?;
+
+		if let Some(index) = self.index {
+			// The pages are opened until the one with the position, the positions of the earlier pages are skipped
+			let mut skipped = 0;
+			loop {
+				if let Some(ln) = index.checked_sub(skipped + 1).and_then(|i| list.get(i)) {
+					return Ok(Some(ln.clone()));
+				}
+				skipped += list.len();
+
+				if index == 0 || list.is_empty() || list.next_page().is_none() {
+					return Err(Failure::new(NOT_FOUND, format!("There is no lightnovel at position {}", index)).into());
+				}
+				list.scrape().await?;
+			}
+		}
+
+		if let Some(ln) = list.iter().find(|ln| ln.get_title().eq_ignore_ascii_case(&self.novel)) {
+			return Ok(Some(ln.clone()));
+		}
+
+		match (list.len(), picker) {
+			(0, _) => Err(Failure::new(NOT_FOUND, format!("There is no lightnovel like {}", self.novel)).into()),
+			(1, _) => Ok(list.first().cloned()),
+			(_, Some(picker)) => show_ln(&mut list, picker).await,
+			(_, None) => {
+				let candidates = list
+					.iter()
+					.enumerate()
+					.map(|(i, ln)| format!("\n{:>4}. {}", i + 1, ln.get_title()))
+					.collect::<String>();
+				let msg = format!("{} lightnovels are like {}, pick one with --index:{}", list.len(), self.novel, candidates);
+				Err(Failure::new(AMBIGUOUS, msg).into())
+			}
+		}
+	}
+}
+
+/// Finds the chapter in the index by its number, its url or its title
+pub fn find_chapter(index: Vec<LightnovelChapter>, query: &str) -> Result<LightnovelChapter, Box<dyn Error>> {
+	let query = query.trim();
+
+	let matches = if let Ok(number) = query.parse::<usize>() {
+		index
+			.into_iter()
+			.filter(|chapter| chapter.get_chapter_number() == number)
+			.collect::<Vec<_>>()
+	} else if is_url(query) {
+		index
+			.into_iter()
+			.filter(|chapter| chapter.get_url().ends_with(query) || query.ends_with(chapter.get_url().as_str()))
+			.collect()
+	} else {
+		let query = query.to_lowercase();
+		let matches = index
+			.into_iter()
+			.filter(|chapter| chapter.get_title().to_lowercase().contains(&query))
+			.collect::<Vec<_>>();
+
+		match matches.iter().position(|chapter| chapter.get_title().to_lowercase() == query) {
+			Some(exact) => vec![matches[exact].clone()],
+			None => matches,
+		}
+	};
+
+	match matches.len() {
+		0 => Err(Failure::new(NOT_FOUND, format!("There is no chapter like {}", query)).into()),
+		1 => Ok(matches.into_iter().next().unwrap()),
+		_ => {
+			let candidates = matches
+				.iter()
+				.map(|chapter| format!("\n{:>6}. {}", chapter.get_chapter_number(), chapter.get_title()))
+				.collect::<String>();
+			let msg = format!("{} chapters are like {}, use the chapter number:{}", matches.len(), query, candidates);
+			Err(Failure::new(AMBIGUOUS, msg).into())
+		}
+	}
+}
+
+/// Menus are only shown if no output format is asked for and a terminal is attached
+pub fn interactive(format: Option<OutputFormat>) -> bool {
+	format.is_none() && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+fn is_url(text: &str) -> bool {
+	text.starts_with("http://") || text.starts_with("https://") || text.starts_with('/')
+}
@@ -0,0 +1,89 @@
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use surf::middleware::{Middleware, Next};
+use surf::utils::async_trait;
+use surf::{Body, Client, Request, Response, StatusCode};
+
+use super::http::settings;
+
+/// What a request fetches, it decides how long the page stays in the cache
+///
+/// Requests without it are never cached.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Page {
+	/// A list of lightnovels or a page of a chapter index
+	Index,
+	Chapter,
+}
+
+impl Page {
+	fn ttl(&self) -> Duration {
+		match self {
+			Page::Index => settings().index_ttl,
+			Page::Chapter => settings().chapter_ttl,
+		}
+	}
+}
+
+/// Answers the requests for pages with the copy in the cache directory while it is fresh
+#[derive(Debug)]
+pub(crate) struct Cache;
+
+#[async_trait]
+impl Middleware for Cache {
+	async fn handle(&self, mut req: Request, client: Client, next: Next<'_>) -> surf::Result<Response> {
+		let ttl = match req.ext::<Page>() {
+			Some(page) => page.ttl(),
+			None => Duration::ZERO,
+		};
+		let dir = match &settings().cache_dir {
+			Some(dir) if !ttl.is_zero() => dir,
+			_ => return next.run(req, client).await,
+		};
+
+		// The chapter index pages are posted to the same url, so the body is part of the key
+		let body = req.take_body().into_string().await?;
+		let key = format!("{} {} {}", req.method(), req.url(), body);
+		req.set_body(body);
+
+		let path = dir.join(format!("{:016x}", fnv(&key)));
+		if let Some(body) = fresh(&path, ttl) {
+			let mut res = surf::http::Response::new(StatusCode::Ok);
+			res.set_body(Body::from_string(body));
+			return Ok(res.into());
+		}
+
+		let mut res = next.run(req, client).await?;
+		if res.status().is_success() {
+			let body = res.body_string().await?;
+			if fs::create_dir_all(dir).is_ok() {
+				// A page which can't be cached is fetched again next time
+				let _ = fs::write(&path, &body);
+			}
+			res.set_body(body);
+		}
+
+		Ok(res)
+	}
+}
+
+/// The cached page if it is younger than the ttl
+fn fresh(path: &Path, ttl: Duration) -> Option<String> {
+	let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+	let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+
+	if age < ttl {
+		fs::read_to_string(path).ok()
+	} else {
+		None
+	}
+}
+
+/// A hash which stays the same between versions of Rust, so the cache stays valid
+pub(crate) fn fnv(text: &str) -> u64 {
+	text
+		.bytes()
+		.fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use http_client::isahc::IsahcClient;
+use http_client::{async_trait, Error, HttpClient, Request, Response};
+use isahc::config::Configurable;
+use surf::{middleware::Redirect, Client, Config, Url};
+
+use super::cache::Cache;
+use super::{CLIENT, HTTP, LIGHTNOVEL_SITE, PLAIN_CLIENT};
+use crate::err::SurfError;
+
+/// How the requests to the site are made, the settings have to be applied before the first request
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+	/// The site the lightnovels are scraped from
+	pub site: String,
+	pub timeout: Option<Duration>,
+	pub user_agent: Option<String>,
+	/// A proxy like `http://localhost:3128` or `socks5://localhost:1080`
+	pub proxy: Option<String>,
+	/// The most requests in a second
+	pub rate_limit: Option<f64>,
+	/// The directory the pages are cached in, nothing is cached without it
+	pub cache_dir: Option<PathBuf>,
+	/// How long the lists of lightnovels and the chapter indexes stay in the cache
+	pub index_ttl: Duration,
+	/// How long the text of the chapters stays in the cache
+	pub chapter_ttl: Duration,
+}
+
+impl Default for HttpConfig {
+	fn default() -> Self {
+		Self {
+			site: LIGHTNOVEL_SITE.to_string(),
+			timeout: Some(Duration::from_secs(30)),
+			user_agent: None,
+			proxy: None,
+			rate_limit: None,
+			cache_dir: None,
+			index_ttl: Duration::ZERO,
+			chapter_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+		}
+	}
+}
+
+impl HttpConfig {
+	/// Makes every request use these settings, it does nothing once a request was made
+	pub fn apply(self) -> bool {
+		HTTP.set(self).is_ok()
+	}
+}
+
+/// The settings of the requests, the default settings if none were applied
+pub(crate) fn settings() -> &'static HttpConfig {
+	HTTP.get_or_init(HttpConfig::default)
+}
+
+/// The site the lightnovels are scraped from without a `/` at the end
+pub(crate) fn site() -> &'static str {
+	settings().site.trim_end_matches('/')
+}
+
+/// The client every request to the site is made with, it is created with the settings on the first request
+pub(crate) fn client() -> Result<&'static Client, SurfError> {
+	CLIENT.get_or_try_init(|| Ok(http_client()?.clone().with(Cache).with(Redirect::new(3))))
+}
+
+/// A client with the timeout, user agent, proxy and rate limit of the settings for requests which aren't scraping
+///
+/// It neither caches the responses nor follows redirects, the redirect middleware would send a request
+/// once without its body first.
+pub fn http_client() -> Result<&'static Client, SurfError> {
+	PLAIN_CLIENT.get_or_try_init(|| {
+		let settings = settings();
+
+		let url = Url::parse(site()).into_report().change_context(SurfError::UriParserError)?;
+
+		let mut builder = isahc::HttpClient::builder();
+		if let Some(timeout) = settings.timeout {
+			builder = builder.timeout(timeout);
+		}
+		if let Some(proxy) = &settings.proxy {
+			let proxy = proxy.parse().into_report().change_context(SurfError::UriParserError)?;
+			builder = builder.proxy(Some(proxy));
+		}
+		let http_client = builder.build().into_report().change_context(SurfError::ClientCreationError)?;
+
+		let mut config = Config::new().set_base_url(url).set_http_client(RateLimited {
+			client: IsahcClient::from_client(http_client),
+			interval: settings
+				.rate_limit
+				.filter(|rate| *rate > 0.0)
+				.map(|rate| Duration::from_secs_f64(1.0 / rate)),
+			next: Mutex::new(Instant::now()),
+		});
+		if let Some(user_agent) = &settings.user_agent {
+			config = match config.add_header("User-Agent", user_agent.as_str()) {
+				Ok(config) => config,
+				Err(_) => return Err(Report::new(SurfError::ClientCreationError).attach_printable(format!("{} is not a user agent", user_agent))),
+			};
+		}
+
+		config.try_into().into_report().change_context(SurfError::ClientCreationError)
+	})
+}
+
+/// Waits between the requests so there are no more requests in a second than the rate limit
+///
+/// It wraps the http client instead of being a middleware, so the requests the redirect middleware
+/// makes on its own are counted too.
+#[derive(Debug)]
+struct RateLimited {
+	client: IsahcClient,
+	interval: Option<Duration>,
+	/// When the next request may be sent
+	next: Mutex<Instant>,
+}
+
+#[async_trait]
+impl HttpClient for RateLimited {
+	async fn send(&self, req: Request) -> std::result::Result<Response, Error> {
+		if let Some(interval) = self.interval {
+			let wait = {
+				let mut next = self.next.lock().unwrap();
+				let now = Instant::now();
+				let at = (*next).max(now);
+				*next = at + interval;
+				at - now
+			};
+
+			if !wait.is_zero() {
+				async_std::task::sleep(wait).await;
+			}
+		}
+
+		self.client.send(req).await
+	}
+}
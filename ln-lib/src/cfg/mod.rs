@@ -1,6 +1,21 @@
+mod cache;
+mod http;
+
 use once_cell::sync::OnceCell;
 use surf::Client;
 
+use crate::store::Store;
+
+pub(crate) use cache::{fnv, Page};
+pub(crate) use http::{client, site};
+pub use http::{http_client, HttpConfig};
+
 pub const LIGHTNOVEL_SITE: &str = "https://readlightnovels.net";
 
+pub static HTTP: OnceCell<HttpConfig> = OnceCell::new();
+
 pub static CLIENT: OnceCell<Client> = OnceCell::new();
+
+pub static PLAIN_CLIENT: OnceCell<Client> = OnceCell::new();
+
+pub static STORE: OnceCell<Store> = OnceCell::new();
@@ -0,0 +1,34 @@
+use super::SurfError;
+use error_stack::Context;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ExportError {
+	ScraperError(SurfError),
+	ReadError(String),
+	WriteError(String),
+	TemplateError(String),
+	NoChapters,
+}
+
+impl fmt::Display for ExportError {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let err_text = match self {
+			Self::ScraperError(_) => format!("Export error: There is an error acourred while trying to download a file for the export"),
+			Self::ReadError(_) => format!("Export error: There is an error acourred while trying to read a file for the export"),
+			Self::WriteError(_) => format!("Export error: There is an error acourred while trying to write the export"),
+			Self::TemplateError(_) => format!("Export error: The file name template gives several chapters the same file"),
+			Self::NoChapters => format!("Export error: There are no chapters to export"),
+		};
+
+		fmt.write_str(&err_text)
+	}
+}
+
+impl Context for ExportError {}
+
+impl From<SurfError> for ExportError {
+	fn from(err: SurfError) -> Self {
+		ExportError::ScraperError(err)
+	}
+}
@@ -1,5 +1,9 @@
+mod exporterror;
 mod scrapererror;
+mod storeerror;
 mod surferror;
 
+pub use exporterror::ExportError;
 pub use scrapererror::*;
+pub use storeerror::StoreError;
 pub use surferror::SurfError;
@@ -1,16 +1,19 @@
-use super::SurfError;
+use super::{StoreError, SurfError};
 use error_stack::Context;
 use std::fmt;
 
 #[derive(Debug)]
 pub enum ChapterError {
 	ScraperError(SurfError),
+	StoreError(StoreError),
 }
 
 impl fmt::Display for ChapterError {
 	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let err_text = match self {
 			Self::ScraperError(_) => format!("Chapter error: There is an error acourred while trying to scrape the content from a chapter"),
+			Self::StoreError(StoreError::NotDownloaded(_)) => format!("Chapter error: The chapter is not downloaded"),
+			Self::StoreError(_) => format!("Chapter error: There is an error acourred while trying to read from the offline store"),
 		};
 
 		fmt.write_str(&err_text)
@@ -24,3 +27,9 @@ impl From<SurfError> for ChapterError {
 		ChapterError::ScraperError(err)
 	}
 }
+
+impl From<StoreError> for ChapterError {
+	fn from(err: StoreError) -> Self {
+		ChapterError::StoreError(err)
+	}
+}
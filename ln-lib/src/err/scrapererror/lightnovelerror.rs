@@ -1,10 +1,11 @@
-use super::SurfError;
+use super::{StoreError, SurfError};
 use error_stack::Context;
 use std::fmt;
 
 #[derive(Debug)]
 pub enum LightnovelError {
 	ScraperError(SurfError),
+	StoreError(StoreError),
 	GetIDError,
 }
 
@@ -13,6 +14,8 @@ impl fmt::Display for LightnovelError {
 		let err_text = match self {
 			Self::GetIDError => format!("Lightnovel error: There is an error acourred while trying to get the Lightnovel ID"),
 			Self::ScraperError(_) => format!("Lightnovel error: There is an error acourred while trying to scrape"),
+			Self::StoreError(StoreError::NotDownloaded(_)) => format!("Lightnovel error: The lightnovel is not downloaded"),
+			Self::StoreError(_) => format!("Lightnovel error: There is an error acourred while trying to read from the offline store"),
 		};
 
 		fmt.write_str(&err_text)
@@ -26,3 +29,9 @@ impl From<SurfError> for LightnovelError {
 		LightnovelError::ScraperError(err)
 	}
 }
+
+impl From<StoreError> for LightnovelError {
+	fn from(err: StoreError) -> Self {
+		LightnovelError::StoreError(err)
+	}
+}
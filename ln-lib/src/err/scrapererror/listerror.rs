@@ -1,16 +1,19 @@
-use super::SurfError;
+use super::{StoreError, SurfError};
 use error_stack::Context;
 use std::fmt;
 
 #[derive(Debug)]
 pub enum ListError {
 	ScraperError(SurfError),
+	StoreError(StoreError),
 }
 
 impl fmt::Display for ListError {
 	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
 		let err_text = match self {
 			Self::ScraperError(_) => format!("List error: There is an error acourred while trying to scrape the lightnovels"),
+			Self::StoreError(StoreError::NotDownloaded(_)) => format!("List error: There are no downloaded lightnovels"),
+			Self::StoreError(_) => format!("List error: There is an error acourred while trying to read from the offline store"),
 		};
 
 		fmt.write_str(&err_text)
@@ -24,3 +27,9 @@ impl From<SurfError> for ListError {
 		ListError::ScraperError(err)
 	}
 }
+
+impl From<StoreError> for ListError {
+	fn from(err: StoreError) -> Self {
+		ListError::StoreError(err)
+	}
+}
@@ -1,4 +1,4 @@
-pub use super::SurfError;
+pub use super::{StoreError, SurfError};
 
 mod chaptererror;
 mod lightnovelerror;
@@ -0,0 +1,25 @@
+use error_stack::Context;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum StoreError {
+	NotDownloaded(String),
+	ReadError(String),
+	WriteError(String),
+	ParseError(String),
+}
+
+impl fmt::Display for StoreError {
+	fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let err_text = match self {
+			Self::NotDownloaded(what) => format!("Store error: {} is not downloaded", what),
+			Self::ReadError(_) => format!("Store error: There acourred a error while reading from the store"),
+			Self::WriteError(_) => format!("Store error: There acourred a error while writing to the store"),
+			Self::ParseError(_) => format!("Store error: There acourred a error while parsing a stored file"),
+		};
+
+		fmt.write_str(&err_text)
+	}
+}
+
+impl Context for StoreError {}
@@ -0,0 +1,129 @@
+//! Calibre metadata and library layout for exports
+//!
+//! Every volume becomes a book folder like Calibre's own `Author/Title (id)/` with a `metadata.opf`,
+//! the `cover.jpg` and the exported file named `Title - Author.ext`, so the folder can be added to a Calibre
+//! library as it is. The id is the one of the lightnovel on the site. Covers which aren't JPEG are left out
+//! since Calibre only picks up `cover.jpg`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error_stack::Result;
+
+use super::{absolute_url, escape_xml, sanitize_file_name, timestamp, write_error, Cover, Volume};
+use crate::err::ExportError;
+use crate::Lightnovel;
+
+/// The folder of the volume in a Calibre library at the root
+pub fn book_dir(root: &Path, ln: &Lightnovel, volume: &Volume) -> PathBuf {
+	let author = ln.get_details().authors.first().map(String::as_str).unwrap_or("Unknown");
+	let title = match ln.get_id() {
+		Some(id) => format!("{} ({})", volume.title, id),
+		None => volume.title.clone(),
+	};
+
+	root.join(sanitize_file_name(author)).join(sanitize_file_name(&title))
+}
+
+/// The name Calibre gives the book file of the volume
+pub fn file_name(ln: &Lightnovel, volume: &Volume, extension: &str) -> String {
+	let author = ln.get_details().authors.first().map(String::as_str).unwrap_or("Unknown");
+	sanitize_file_name(&format!("{} - {}.{}", volume.title, author, extension))
+}
+
+/// Writes the `metadata.opf` and `cover.jpg` of the volume into its folder and returns the folder
+pub fn write_book(root: &Path, ln: &Lightnovel, volume: &Volume, cover: Option<&Cover>) -> Result<PathBuf, ExportError> {
+	let dir = book_dir(root, ln, volume);
+	if fs::create_dir_all(&dir).is_err() {
+		return write_error(&dir);
+	}
+
+	let cover = cover.filter(|cover| cover.data.starts_with(&[0xFF, 0xD8]));
+	if let Some(cover) = cover {
+		let path = dir.join("cover.jpg");
+		if fs::write(&path, &cover.data).is_err() {
+			return write_error(&path);
+		}
+	}
+
+	let path = dir.join("metadata.opf");
+	if fs::write(&path, metadata(ln, volume, cover.is_some())).is_err() {
+		return write_error(&path);
+	}
+
+	Ok(dir)
+}
+
+pub fn metadata(ln: &Lightnovel, volume: &Volume, cover: bool) -> String {
+	let details = ln.get_details();
+	let url = absolute_url(ln.get_url());
+
+	let mut metadata = String::new();
+	let _ = writeln!(
+		metadata,
+		"\t\t<dc:identifier id=\"source_id\" opf:scheme=\"url\">{}</dc:identifier>",
+		escape_xml(&url)
+	);
+	if let Some(id) = ln.get_id() {
+		let _ = writeln!(
+			metadata,
+			"\t\t<dc:identifier opf:scheme=\"ln-scraper\">{}</dc:identifier>",
+			escape_xml(&format!("{}#volume-{}", id, volume.number))
+		);
+	}
+	let _ = writeln!(metadata, "\t\t<dc:title>{}</dc:title>", escape_xml(&volume.title));
+	for author in &details.authors {
+		let _ = writeln!(
+			metadata,
+			"\t\t<dc:creator opf:role=\"aut\" opf:file-as=\"{}\">{}</dc:creator>",
+			escape_xml(&file_as(author)),
+			escape_xml(author)
+		);
+	}
+	for genre in &details.genres {
+		let _ = writeln!(metadata, "\t\t<dc:subject>{}</dc:subject>", escape_xml(genre));
+	}
+	if !details.synopsis.is_empty() {
+		// Calibre shows the description as HTML
+		let description = details
+			.synopsis
+			.iter()
+			.map(|paragraph| format!("<p>{}</p>", escape_xml(paragraph)))
+			.collect::<String>();
+		let _ = writeln!(metadata, "\t\t<dc:description>{}</dc:description>", escape_xml(&description));
+	}
+	let _ = writeln!(metadata, "\t\t<dc:source>{}</dc:source>", escape_xml(&url));
+	let _ = writeln!(metadata, "\t\t<dc:date>{}</dc:date>", timestamp());
+	let _ = writeln!(metadata, "\t\t<meta name=\"calibre:series\" content=\"{}\"/>", escape_xml(ln.get_title()));
+	let _ = writeln!(metadata, "\t\t<meta name=\"calibre:series_index\" content=\"{}\"/>", volume.number);
+	let _ = writeln!(
+		metadata,
+		"\t\t<meta name=\"calibre:title_sort\" content=\"{}\"/>",
+		escape_xml(&volume.title)
+	);
+
+	let guide = if cover {
+		"\t<guide>\n\t\t<reference type=\"cover\" title=\"Cover\" href=\"cover.jpg\"/>\n\t</guide>\n"
+	} else {
+		""
+	};
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" unique-identifier="source_id" version="2.0">
+	<metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+{}	</metadata>
+{}</package>
+"#,
+		metadata, guide
+	)
+}
+
+/// Calibre sorts authors by their last name like `Last, First`
+fn file_as(author: &str) -> String {
+	match author.trim().rsplit_once(' ') {
+		Some((first, last)) => format!("{}, {}", last.trim(), first.trim()),
+		None => author.trim().to_string(),
+	}
+}
@@ -0,0 +1,635 @@
+//! EPUB 3 export of a lightnovel
+//!
+//! Every volume becomes one EPUB with a title page, a navigation document, a NCX table of contents
+//! for older readers and one XHTML document per chapter.
+//!
+//! An EPUB written by this module can be updated: the chapters it holds are found by the chapter URL every
+//! chapter document carries, only the missing ones are added and the identifier of the book is kept.
+//! The chapter documents are named after a hash of their URL, the chapter numbers of a site can change.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, Result};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{absolute_url, escape_xml, read_error, sanitize_file_name, timestamp, write_error, Cover, Volume, VolumeRule};
+use crate::cfg::fnv;
+use crate::err::ExportError;
+use crate::{Lightnovel, LightnovelChapter};
+
+pub const DEFAULT_CSS: &str = "body { font-family: serif; line-height: 1.5; margin: 0 5%; }
+h1, h2 { text-align: center; }
+p { text-indent: 1.5em; margin: 0 0 0.5em 0; }
+.title-page { text-align: center; }
+.title-page p { text-indent: 0; }
+.synopsis p { text-align: justify; }
+.cover { max-width: 100%; max-height: 60vh; }
+";
+
+#[derive(Debug, Clone)]
+pub struct EpubOptions {
+	pub css: Option<String>,
+	pub cover: Option<Cover>,
+	pub volumes: VolumeRule,
+	pub language: String,
+	/// Add the new chapters to EPUBs which already exist instead of writing them again
+	pub update: bool,
+}
+
+impl Default for EpubOptions {
+	fn default() -> Self {
+		Self {
+			css: None,
+			cover: None,
+			volumes: VolumeRule::default(),
+			language: "en".to_string(),
+			update: false,
+		}
+	}
+}
+
+struct TocEntry {
+	id: String,
+	href: String,
+	title: String,
+}
+
+/// Writes one EPUB per volume into the directory and returns the paths of the written files
+pub fn export(ln: &Lightnovel, chapters: &[LightnovelChapter], options: &EpubOptions, dir: &Path) -> Result<Vec<PathBuf>, ExportError> {
+	export_to(ln, chapters, options, |volume| dir.join(file_name(volume)))
+}
+
+/// Writes the EPUB of every volume to the path `path_of` gives it and returns the paths of the written files
+pub fn export_to<F>(ln: &Lightnovel, chapters: &[LightnovelChapter], options: &EpubOptions, path_of: F) -> Result<Vec<PathBuf>, ExportError>
+where
+	F: Fn(&Volume) -> PathBuf,
+{
+	let volumes = options.volumes.split(ln, chapters);
+	if volumes.is_empty() {
+		return Err(Report::new(ExportError::NoChapters));
+	}
+
+	let mut paths = Vec::with_capacity(volumes.len());
+	for volume in volumes {
+		let path = path_of(&volume);
+		if options.update && path.exists() {
+			update(ln, &volume, options, &path)?;
+			paths.push(path);
+			continue;
+		}
+
+		if let Some(dir) = path.parent().filter(|dir| fs::create_dir_all(dir).is_err()) {
+			return write_error(dir);
+		}
+		let file = match File::create(&path) {
+			Ok(file) => file,
+			Err(_) => return write_error(&path),
+		};
+
+		write(ln, &volume, options, &identifier(ln, &volume), file)?;
+		paths.push(path);
+	}
+
+	Ok(paths)
+}
+
+/// The name of the EPUB of a volume in the export directory
+pub fn file_name(volume: &Volume) -> String {
+	format!("{}.epub", sanitize_file_name(&volume.title))
+}
+
+/// The identifier of the EPUB of a volume, it stays the same between exports
+pub fn identifier(ln: &Lightnovel, volume: &Volume) -> String {
+	format!("urn:ln-scraper:{}#volume-{}", absolute_url(ln.get_url()), volume.number)
+}
+
+pub fn write<W: Write + Seek>(ln: &Lightnovel, volume: &Volume, options: &EpubOptions, identifier: &str, writer: W) -> Result<(), ExportError> {
+	let mut zip = ZipWriter::new(writer);
+
+	let entries = volume
+		.chapters
+		.iter()
+		.map(|chapter| TocEntry {
+			id: chapter_id(chapter),
+			href: chapter_href(chapter),
+			title: chapter.get_title().clone(),
+		})
+		.collect::<Vec<_>>();
+
+	add_file(&mut zip, "mimetype", b"application/epub+zip")?;
+	add_file(&mut zip, "META-INF/container.xml", CONTAINER.as_bytes())?;
+	add_file(&mut zip, "OEBPS/style.css", options.css.as_deref().unwrap_or(DEFAULT_CSS).as_bytes())?;
+
+	if let Some(cover) = &options.cover {
+		add_file(&mut zip, &format!("OEBPS/cover.{}", cover.extension()), &cover.data)?;
+	}
+
+	let package = package(ln, &volume.title, identifier, &entries, options);
+	add_file(&mut zip, "OEBPS/content.opf", package.as_bytes())?;
+	add_file(&mut zip, "OEBPS/nav.xhtml", nav(&volume.title, &entries, &options.language).as_bytes())?;
+	add_file(&mut zip, "OEBPS/toc.ncx", ncx(&volume.title, identifier, &entries).as_bytes())?;
+	add_file(&mut zip, "OEBPS/title.xhtml", title_page(ln, &volume.title, options).as_bytes())?;
+
+	for chapter in &volume.chapters {
+		let page = chapter_page(chapter, &options.language);
+		add_file(&mut zip, &format!("OEBPS/{}", chapter_href(chapter)), page.as_bytes())?;
+	}
+
+	if zip.finish().is_err() {
+		let msg = "There was a problem with finishing the epub".to_string();
+		return Err(Report::new(ExportError::WriteError(msg.clone())).attach_printable(msg));
+	}
+
+	Ok(())
+}
+
+/// Adds the chapters of the volume the EPUB at the path is missing and returns how many were added
+pub fn update(ln: &Lightnovel, volume: &Volume, options: &EpubOptions, path: &Path) -> Result<usize, ExportError> {
+	let mut archive = match File::open(path).map(ZipArchive::new) {
+		Ok(Ok(archive)) => archive,
+		_ => return read_error(path),
+	};
+
+	let opf = match read_entry(&mut archive, "OEBPS/content.opf") {
+		Some(opf) => opf,
+		None => return read_error(path),
+	};
+	let identifier = match between(&opf, "<dc:identifier id=\"book-id\">", "</dc:identifier>") {
+		Some(identifier) => unescape_xml(identifier),
+		None => {
+			let msg = format!("The epub wasn't exported by ln-scraper: {}", path.display());
+			return Err(Report::new(ExportError::ReadError(msg.clone())).attach_printable(msg));
+		}
+	};
+	let title = between(&opf, "<dc:title>", "</dc:title>")
+		.map(unescape_xml)
+		.unwrap_or_else(|| volume.title.clone());
+
+	let (mut entries, contents) = chapter_entries(&mut archive, path)?;
+
+	let added = volume.chapters.iter().filter(|chapter| !contents.contains(chapter)).collect::<Vec<_>>();
+	if added.is_empty() {
+		return Ok(0);
+	}
+
+	for chapter in &added {
+		entries.push((
+			chapter.get_chapter_number(),
+			TocEntry {
+				id: chapter_id(chapter),
+				href: chapter_href(chapter),
+				title: chapter.get_title().clone(),
+			},
+		));
+	}
+	entries.sort_by_key(|(number, _)| *number);
+	let entries = entries.into_iter().map(|(_, entry)| entry).collect::<Vec<_>>();
+
+	// The cover of the EPUB is kept if there is no new one
+	let mut options = options.clone();
+	if options.cover.is_none() {
+		let cover = archive.file_names().find(|name| name.starts_with("OEBPS/cover.")).map(String::from);
+		if let Some(name) = cover {
+			let mut data = Vec::new();
+			if archive.by_name(&name).map(|mut file| file.read_to_end(&mut data)).is_err() {
+				return read_error(path);
+			}
+			options.cover = Some(Cover::from_extension(data, &name["OEBPS/cover.".len()..]));
+		}
+	}
+
+	let temp = path.with_extension("epub.part");
+	let file = match File::create(&temp) {
+		Ok(file) => file,
+		Err(_) => return write_error(&temp),
+	};
+	let mut zip = ZipWriter::new(file);
+
+	// Everything that doesn't change is copied without recompressing it
+	for i in 0..archive.len() {
+		let file = match archive.by_index_raw(i) {
+			Ok(file) => file,
+			Err(_) => return read_error(path),
+		};
+
+		let name = file.name();
+		let rewritten = matches!(name, "OEBPS/content.opf" | "OEBPS/nav.xhtml" | "OEBPS/toc.ncx" | "OEBPS/title.xhtml")
+			|| name.starts_with("OEBPS/cover.")
+			|| (name == "OEBPS/style.css" && options.css.is_some());
+		if rewritten {
+			continue;
+		}
+
+		if zip.raw_copy_file(file).is_err() {
+			return write_error(&temp);
+		}
+	}
+
+	if let Some(css) = &options.css {
+		add_file(&mut zip, "OEBPS/style.css", css.as_bytes())?;
+	}
+	if let Some(cover) = &options.cover {
+		add_file(&mut zip, &format!("OEBPS/cover.{}", cover.extension()), &cover.data)?;
+	}
+
+	let package = package(ln, &title, &identifier, &entries, &options);
+	add_file(&mut zip, "OEBPS/content.opf", package.as_bytes())?;
+	add_file(&mut zip, "OEBPS/nav.xhtml", nav(&title, &entries, &options.language).as_bytes())?;
+	add_file(&mut zip, "OEBPS/toc.ncx", ncx(&title, &identifier, &entries).as_bytes())?;
+	add_file(&mut zip, "OEBPS/title.xhtml", title_page(ln, &title, &options).as_bytes())?;
+
+	for chapter in &added {
+		let page = chapter_page(chapter, &options.language);
+		add_file(&mut zip, &format!("OEBPS/{}", chapter_href(chapter)), page.as_bytes())?;
+	}
+
+	if zip.finish().is_err() || fs::rename(&temp, path).is_err() {
+		let _ = fs::remove_file(&temp);
+		return write_error(path);
+	}
+
+	Ok(added.len())
+}
+
+/// The chapters an EPUB written by this module holds
+#[derive(Debug, Clone, Default)]
+pub struct Contents {
+	/// The absolute URLs of the chapters
+	pub urls: HashSet<String>,
+}
+
+impl Contents {
+	/// Whether the chapter is in the EPUB by its URL
+	pub fn contains(&self, chapter: &LightnovelChapter) -> bool {
+		self.urls.contains(&absolute_url(chapter.get_url()))
+	}
+}
+
+/// Reads which chapters the EPUB at the path holds without changing it
+pub fn contents(path: &Path) -> Result<Contents, ExportError> {
+	let mut archive = match File::open(path).map(ZipArchive::new) {
+		Ok(Ok(archive)) => archive,
+		_ => return read_error(path),
+	};
+
+	Ok(chapter_entries(&mut archive, path)?.1)
+}
+
+/// The table of contents entries of the chapter documents in the archive with the chapter numbers to sort them
+fn chapter_entries<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &Path) -> Result<(Vec<(usize, TocEntry)>, Contents), ExportError> {
+	let names = archive
+		.file_names()
+		.filter(|name| name.starts_with("OEBPS/chapters/") && name.ends_with(".xhtml"))
+		.map(String::from)
+		.collect::<Vec<_>>();
+
+	let mut entries = Vec::with_capacity(names.len());
+	let mut contents = Contents::default();
+	for name in names {
+		let page = match read_entry(archive, &name) {
+			Some(page) => page,
+			None => return read_error(path),
+		};
+		let url = match between(&page, "<meta name=\"ln-scraper:chapter-url\" content=\"", "\"/>") {
+			Some(url) => unescape_xml(url),
+			None => continue,
+		};
+		let number = between(&page, "<meta name=\"ln-scraper:chapter-number\" content=\"", "\"/>")
+			.and_then(|number| number.parse().ok())
+			.unwrap_or_default();
+
+		contents.urls.insert(absolute_url(&url));
+		entries.push((
+			number,
+			TocEntry {
+				id: name["OEBPS/chapters/".len()..name.len() - ".xhtml".len()].to_string(),
+				href: name["OEBPS/".len()..].to_string(),
+				title: between(&page, "<title>", "</title>").map(unescape_xml).unwrap_or_default(),
+			},
+		));
+	}
+
+	Ok((entries, contents))
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+	let mut content = String::new();
+	archive.by_name(name).ok()?.read_to_string(&mut content).ok()?;
+	Some(content)
+}
+
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+	let rest = &text[text.find(start)? + start.len()..];
+	Some(&rest[..rest.find(end)?])
+}
+
+fn unescape_xml(text: &str) -> String {
+	text
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&apos;", "'")
+		.replace("&amp;", "&")
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+	<rootfiles>
+		<rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+	</rootfiles>
+</container>
+"#;
+
+fn add_file<W: Write + Seek>(zip: &mut ZipWriter<W>, name: &str, content: &[u8]) -> Result<(), ExportError> {
+	let options = match name {
+		"mimetype" => FileOptions::default().compression_method(CompressionMethod::Stored),
+		_ => FileOptions::default().compression_method(CompressionMethod::Deflated),
+	};
+
+	if zip.start_file(name, options).is_err() || zip.write_all(content).is_err() {
+		let msg = format!("There was a problem with writing {} into the epub", name);
+		return Err(Report::new(ExportError::WriteError(msg.clone())).attach_printable(msg));
+	}
+
+	Ok(())
+}
+
+fn chapter_id(chapter: &LightnovelChapter) -> String {
+	format!("chapter-{:016x}", fnv(&absolute_url(chapter.get_url())))
+}
+
+fn chapter_href(chapter: &LightnovelChapter) -> String {
+	format!("chapters/{}.xhtml", chapter_id(chapter))
+}
+
+fn package(ln: &Lightnovel, title: &str, identifier: &str, entries: &[TocEntry], options: &EpubOptions) -> String {
+	let details = ln.get_details();
+
+	let mut metadata = String::new();
+	let _ = writeln!(metadata, "\t\t<dc:identifier id=\"book-id\">{}</dc:identifier>", escape_xml(identifier));
+	let _ = writeln!(metadata, "\t\t<dc:title>{}</dc:title>", escape_xml(title));
+	let _ = writeln!(metadata, "\t\t<dc:language>{}</dc:language>", escape_xml(&options.language));
+	for author in &details.authors {
+		let _ = writeln!(metadata, "\t\t<dc:creator>{}</dc:creator>", escape_xml(author));
+	}
+	for genre in &details.genres {
+		let _ = writeln!(metadata, "\t\t<dc:subject>{}</dc:subject>", escape_xml(genre));
+	}
+	if !details.synopsis.is_empty() {
+		let _ = writeln!(
+			metadata,
+			"\t\t<dc:description>{}</dc:description>",
+			escape_xml(&details.synopsis.join("\n\n"))
+		);
+	}
+	let _ = writeln!(metadata, "\t\t<dc:source>{}</dc:source>", escape_xml(&absolute_url(ln.get_url())));
+	let _ = writeln!(metadata, "\t\t<meta property=\"dcterms:modified\">{}</meta>", timestamp());
+
+	let mut manifest = String::new();
+	let _ = writeln!(
+		manifest,
+		"\t\t<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>"
+	);
+	let _ = writeln!(
+		manifest,
+		"\t\t<item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>"
+	);
+	let _ = writeln!(manifest, "\t\t<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>");
+	let _ = writeln!(
+		manifest,
+		"\t\t<item id=\"title-page\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>"
+	);
+	if let Some(cover) = &options.cover {
+		let _ = writeln!(metadata, "\t\t<meta name=\"cover\" content=\"cover-image\"/>");
+		let _ = writeln!(
+			manifest,
+			"\t\t<item id=\"cover-image\" href=\"cover.{}\" media-type=\"{}\" properties=\"cover-image\"/>",
+			cover.extension(),
+			escape_xml(&cover.media_type)
+		);
+	}
+
+	let mut spine = String::new();
+	let _ = writeln!(spine, "\t\t<itemref idref=\"title-page\"/>");
+	let _ = writeln!(spine, "\t\t<itemref idref=\"nav\"/>");
+	for entry in entries {
+		let _ = writeln!(
+			manifest,
+			"\t\t<item id=\"{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+			entry.id, entry.href
+		);
+		let _ = writeln!(spine, "\t\t<itemref idref=\"{}\"/>", entry.id);
+	}
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{}">
+	<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+{}	</metadata>
+	<manifest>
+{}	</manifest>
+	<spine toc="ncx">
+{}	</spine>
+</package>
+"#,
+		escape_xml(&options.language),
+		metadata,
+		manifest,
+		spine
+	)
+}
+
+fn nav(title: &str, entries: &[TocEntry], language: &str) -> String {
+	let mut items = String::new();
+	let _ = writeln!(items, "\t\t\t\t<li><a href=\"title.xhtml\">{}</a></li>", escape_xml(title));
+	for entry in entries {
+		let _ = writeln!(items, "\t\t\t\t<li><a href=\"{}\">{}</a></li>", entry.href, escape_xml(&entry.title));
+	}
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{0}" lang="{0}">
+	<head>
+		<meta charset="UTF-8"/>
+		<title>{1}</title>
+		<link rel="stylesheet" type="text/css" href="style.css"/>
+	</head>
+	<body>
+		<nav epub:type="toc" id="toc">
+			<h1>Contents</h1>
+			<ol>
+{2}			</ol>
+		</nav>
+	</body>
+</html>
+"#,
+		escape_xml(language),
+		escape_xml(title),
+		items
+	)
+}
+
+fn ncx(title: &str, identifier: &str, entries: &[TocEntry]) -> String {
+	let mut points = String::new();
+	let _ = writeln!(
+		points,
+		"\t\t<navPoint id=\"title-page\" playOrder=\"1\"><navLabel><text>{}</text></navLabel><content src=\"title.xhtml\"/></navPoint>",
+		escape_xml(title)
+	);
+	for (i, entry) in entries.iter().enumerate() {
+		let _ = writeln!(
+			points,
+			"\t\t<navPoint id=\"{}\" playOrder=\"{}\"><navLabel><text>{}</text></navLabel><content src=\"{}\"/></navPoint>",
+			entry.id,
+			i + 2,
+			escape_xml(&entry.title),
+			entry.href
+		);
+	}
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+	<head>
+		<meta name="dtb:uid" content="{}"/>
+		<meta name="dtb:depth" content="1"/>
+		<meta name="dtb:totalPageCount" content="0"/>
+		<meta name="dtb:maxPageNumber" content="0"/>
+	</head>
+	<docTitle><text>{}</text></docTitle>
+	<navMap>
+{}	</navMap>
+</ncx>
+"#,
+		escape_xml(identifier),
+		escape_xml(title),
+		points
+	)
+}
+
+fn title_page(ln: &Lightnovel, title: &str, options: &EpubOptions) -> String {
+	let details = ln.get_details();
+
+	let mut body = String::new();
+	if let Some(cover) = &options.cover {
+		let _ = writeln!(body, "\t\t\t<img class=\"cover\" src=\"cover.{}\" alt=\"Cover\"/>", cover.extension());
+	}
+	let _ = writeln!(body, "\t\t\t<h1>{}</h1>", escape_xml(title));
+	if !details.authors.is_empty() {
+		let _ = writeln!(body, "\t\t\t<p>{}</p>", escape_xml(&details.authors.join(", ")));
+	}
+	if !details.genres.is_empty() {
+		let _ = writeln!(body, "\t\t\t<p>{}</p>", escape_xml(&details.genres.join(", ")));
+	}
+	if let Some(status) = &details.status {
+		let _ = writeln!(body, "\t\t\t<p>{}</p>", escape_xml(status));
+	}
+	if !details.synopsis.is_empty() {
+		let _ = writeln!(body, "\t\t\t<div class=\"synopsis\">");
+		for paragraph in &details.synopsis {
+			let _ = writeln!(body, "\t\t\t\t<p>{}</p>", escape_xml(paragraph));
+		}
+		let _ = writeln!(body, "\t\t\t</div>");
+	}
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{0}" lang="{0}">
+	<head>
+		<meta charset="UTF-8"/>
+		<title>{1}</title>
+		<link rel="stylesheet" type="text/css" href="style.css"/>
+	</head>
+	<body>
+		<section epub:type="titlepage" class="title-page">
+{2}		</section>
+	</body>
+</html>
+"#,
+		escape_xml(&options.language),
+		escape_xml(title),
+		body
+	)
+}
+
+fn chapter_page(chapter: &LightnovelChapter, language: &str) -> String {
+	let mut body = String::new();
+	for paragraph in chapter.iter() {
+		let lines = paragraph.split('\n').map(escape_xml).collect::<Vec<_>>();
+		let _ = writeln!(body, "\t\t\t<p>{}</p>", lines.join("<br/>"));
+	}
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{0}" lang="{0}">
+	<head>
+		<meta charset="UTF-8"/>
+		<meta name="ln-scraper:chapter-url" content="{1}"/>
+		<meta name="ln-scraper:chapter-number" content="{5}"/>
+		<title>{2}</title>
+		<link rel="stylesheet" type="text/css" href="../style.css"/>
+	</head>
+	<body>
+		<section epub:type="chapter" id="{3}">
+			<h2>{2}</h2>
+{4}		</section>
+	</body>
+</html>
+"#,
+		escape_xml(language),
+		escape_xml(chapter.get_url()),
+		escape_xml(chapter.get_title()),
+		chapter_id(chapter),
+		body,
+		chapter.get_chapter_number()
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chapter(number: usize) -> LightnovelChapter {
+		let mut chapter = LightnovelChapter::new(format!("Chapter {}", number), format!("/novel/x/chapter-{}", number), number);
+		chapter.push("Text.".to_string());
+		chapter
+	}
+
+	#[test]
+	fn update_adds_only_the_missing_chapters() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let dir = std::env::temp_dir().join(format!("ln-lib-epub-{}", std::process::id()));
+		let options = EpubOptions {
+			update: true,
+			..Default::default()
+		};
+
+		let paths = export(&ln, &[chapter(1), chapter(2)], &options, &dir).unwrap();
+		let first = contents(&paths[0]).unwrap();
+		assert!(first.contains(&chapter(2)));
+		assert!(!first.contains(&chapter(3)));
+
+		let chapters = [chapter(1), chapter(2), chapter(3)];
+		let volume = VolumeRule::Single.split(&ln, &chapters).remove(0);
+		assert_eq!(update(&ln, &volume, &options, &paths[0]).unwrap(), 1);
+		assert_eq!(update(&ln, &volume, &options, &paths[0]).unwrap(), 0);
+		assert_eq!(contents(&paths[0]).unwrap().urls.len(), 3);
+
+		// A chapter is matched by its URL, not by its number
+		let renumbered = LightnovelChapter::new("Chapter 1".to_string(), "/novel/x/chapter-1".to_string(), 7);
+		assert!(contents(&paths[0]).unwrap().contains(&renumbered));
+		assert!(!contents(&paths[0])
+			.unwrap()
+			.contains(&LightnovelChapter::new("Side story".to_string(), "/novel/x/side".to_string(), 2)));
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
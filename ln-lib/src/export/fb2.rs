@@ -0,0 +1,165 @@
+//! FB2 (FictionBook 2.0) export of a lightnovel
+//!
+//! The details of the lightnovel become the `<description>`, every chapter a `<section>` and every
+//! paragraph a `<p>`. The cover is embedded as a base64 `<binary>`.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, Result};
+
+use super::{absolute_url, escape_xml, sanitize_file_name, timestamp, write_error, Cover, Volume, VolumeRule};
+use crate::err::ExportError;
+use crate::{Lightnovel, LightnovelChapter};
+
+#[derive(Debug, Clone)]
+pub struct Fb2Options {
+	pub cover: Option<Cover>,
+	pub volumes: VolumeRule,
+	pub language: String,
+}
+
+impl Default for Fb2Options {
+	fn default() -> Self {
+		Self {
+			cover: None,
+			volumes: VolumeRule::default(),
+			language: "en".to_string(),
+		}
+	}
+}
+
+/// Writes one FB2 file per volume into the directory and returns the paths of the written files
+pub fn export(ln: &Lightnovel, chapters: &[LightnovelChapter], options: &Fb2Options, dir: &Path) -> Result<Vec<PathBuf>, ExportError> {
+	let volumes = options.volumes.split(ln, chapters);
+	if volumes.is_empty() {
+		return Err(Report::new(ExportError::NoChapters));
+	}
+
+	if fs::create_dir_all(dir).is_err() {
+		return write_error(dir);
+	}
+
+	let mut paths = Vec::with_capacity(volumes.len());
+	for volume in volumes {
+		let path = dir.join(format!("{}.fb2", sanitize_file_name(&volume.title)));
+		if fs::write(&path, render(ln, &volume, options)).is_err() {
+			return write_error(&path);
+		}
+		paths.push(path);
+	}
+
+	Ok(paths)
+}
+
+pub fn render(ln: &Lightnovel, volume: &Volume, options: &Fb2Options) -> String {
+	let details = ln.get_details();
+	let cover_id = options.cover.as_ref().map(|cover| format!("cover.{}", cover.extension()));
+
+	let mut title_info = String::new();
+	let _ = writeln!(title_info, "\t\t\t<genre>prose_contemporary</genre>");
+	for author in &details.authors {
+		let _ = writeln!(title_info, "\t\t\t<author>{}</author>", author_name(author));
+	}
+	if details.authors.is_empty() {
+		let _ = writeln!(title_info, "\t\t\t<author><nickname>Unknown</nickname></author>");
+	}
+	let _ = writeln!(title_info, "\t\t\t<book-title>{}</book-title>", escape_xml(&volume.title));
+	if !details.synopsis.is_empty() {
+		let _ = writeln!(title_info, "\t\t\t<annotation>");
+		for paragraph in &details.synopsis {
+			let _ = writeln!(title_info, "\t\t\t\t<p>{}</p>", escape_xml(paragraph));
+		}
+		let _ = writeln!(title_info, "\t\t\t</annotation>");
+	}
+	if !details.genres.is_empty() {
+		let _ = writeln!(title_info, "\t\t\t<keywords>{}</keywords>", escape_xml(&details.genres.join(", ")));
+	}
+	if let Some(cover_id) = &cover_id {
+		let _ = writeln!(title_info, "\t\t\t<coverpage><image l:href=\"#{}\"/></coverpage>", cover_id);
+	}
+	let _ = writeln!(title_info, "\t\t\t<lang>{}</lang>", escape_xml(&options.language));
+
+	let date = timestamp()[..10].to_string();
+	let mut document_info = String::new();
+	let _ = writeln!(document_info, "\t\t\t<author><nickname>ln-scraper</nickname></author>");
+	let _ = writeln!(document_info, "\t\t\t<program-used>ln-scraper</program-used>");
+	let _ = writeln!(document_info, "\t\t\t<date value=\"{0}\">{0}</date>", date);
+	let _ = writeln!(document_info, "\t\t\t<src-url>{}</src-url>", escape_xml(&absolute_url(ln.get_url())));
+	let _ = writeln!(
+		document_info,
+		"\t\t\t<id>{}</id>",
+		escape_xml(&format!("{}#volume-{}", absolute_url(ln.get_url()), volume.number))
+	);
+	let _ = writeln!(document_info, "\t\t\t<version>1.0</version>");
+
+	let mut body = String::new();
+	for chapter in &volume.chapters {
+		let _ = writeln!(body, "\t\t<section id=\"chapter-{}\">", chapter.get_chapter_number());
+		let _ = writeln!(body, "\t\t\t<title><p>{}</p></title>", escape_xml(chapter.get_title()));
+		for paragraph in chapter.iter() {
+			for line in paragraph.split('\n') {
+				if line.trim().is_empty() {
+					let _ = writeln!(body, "\t\t\t<empty-line/>");
+				} else {
+					let _ = writeln!(body, "\t\t\t<p>{}</p>", escape_xml(line.trim()));
+				}
+			}
+		}
+		let _ = writeln!(body, "\t\t</section>");
+	}
+
+	let mut binaries = String::new();
+	if let (Some(cover), Some(cover_id)) = (&options.cover, &cover_id) {
+		let _ = writeln!(
+			binaries,
+			"\t<binary id=\"{}\" content-type=\"{}\">{}</binary>",
+			cover_id,
+			escape_xml(&cover.media_type),
+			base64::encode(&cover.data)
+		);
+	}
+
+	format!(
+		r#"<?xml version="1.0" encoding="UTF-8"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+	<description>
+		<title-info>
+{}		</title-info>
+		<document-info>
+{}		</document-info>
+	</description>
+	<body>
+		<title><p>{}</p></title>
+{}	</body>
+{}</FictionBook>
+"#,
+		title_info,
+		document_info,
+		escape_xml(&volume.title),
+		body,
+		binaries
+	)
+}
+
+/// FB2 wants a first and last name, single names are used as nickname
+fn author_name(author: &str) -> String {
+	let names = author.split_whitespace().collect::<Vec<_>>();
+
+	match names.as_slice() {
+		[] => "<nickname>Unknown</nickname>".to_string(),
+		[name] => format!("<nickname>{}</nickname>", escape_xml(name)),
+		[first, last] => format!(
+			"<first-name>{}</first-name><last-name>{}</last-name>",
+			escape_xml(first),
+			escape_xml(last)
+		),
+		[first, middle @ .., last] => format!(
+			"<first-name>{}</first-name><middle-name>{}</middle-name><last-name>{}</last-name>",
+			escape_xml(first),
+			escape_xml(&middle.join(" ")),
+			escape_xml(last)
+		),
+	}
+}
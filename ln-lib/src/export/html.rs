@@ -0,0 +1,186 @@
+//! Self-contained HTML export of a lightnovel
+//!
+//! Every volume becomes one HTML file with the stylesheet, the table of contents and the cover embedded,
+//! so it can be opened in any browser without other files. The chapters only hold the text the scraper keeps,
+//! so the cover is the only image that gets embedded.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, Result};
+
+use super::{escape_xml, sanitize_file_name, write_error, Cover, Volume, VolumeRule};
+use crate::err::ExportError;
+use crate::{Lightnovel, LightnovelChapter};
+
+pub const DEFAULT_CSS: &str = ":root { --fg: #1d1d1f; --bg: #fbfaf7; --dim: #6e6e73; --link: #2f5d9b; }
+@media (prefers-color-scheme: dark) { :root { --fg: #d8d8d8; --bg: #161617; --dim: #8e8e93; --link: #8ab4f8; } }
+:root[data-theme=light] { --fg: #1d1d1f; --bg: #fbfaf7; --dim: #6e6e73; --link: #2f5d9b; }
+:root[data-theme=dark] { --fg: #d8d8d8; --bg: #161617; --dim: #8e8e93; --link: #8ab4f8; }
+body { color: var(--fg); background: var(--bg); font-family: Georgia, serif; line-height: 1.6; max-width: 42em; margin: 0 auto; padding: 1em; }
+a { color: var(--link); text-decoration: none; }
+h1, h2 { text-align: center; }
+.title-page { text-align: center; }
+.meta { color: var(--dim); }
+.synopsis { text-align: justify; }
+.cover { max-width: 100%; max-height: 70vh; }
+.chapter { margin-top: 4em; }
+.chapter p { text-indent: 1.5em; }
+.back { text-align: right; font-size: 0.8em; }
+#theme { position: fixed; top: 1em; right: 1em; color: var(--fg); background: var(--bg); border: 1px solid var(--dim); border-radius: 4px; cursor: pointer; }
+";
+
+const THEME_SCRIPT: &str = "document.getElementById('theme').addEventListener('click', function () {
+	var root = document.documentElement;
+	var dark = root.dataset.theme ? root.dataset.theme === 'dark' : window.matchMedia('(prefers-color-scheme: dark)').matches;
+	root.dataset.theme = dark ? 'light' : 'dark';
+});";
+
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+	pub css: Option<String>,
+	pub cover: Option<Cover>,
+	pub volumes: VolumeRule,
+	pub language: String,
+}
+
+impl Default for HtmlOptions {
+	fn default() -> Self {
+		Self {
+			css: None,
+			cover: None,
+			volumes: VolumeRule::default(),
+			language: "en".to_string(),
+		}
+	}
+}
+
+/// Writes one HTML file per volume into the directory and returns the paths of the written files
+pub fn export(ln: &Lightnovel, chapters: &[LightnovelChapter], options: &HtmlOptions, dir: &Path) -> Result<Vec<PathBuf>, ExportError> {
+	let volumes = options.volumes.split(ln, chapters);
+	if volumes.is_empty() {
+		return Err(Report::new(ExportError::NoChapters));
+	}
+
+	if fs::create_dir_all(dir).is_err() {
+		return write_error(dir);
+	}
+
+	let mut paths = Vec::with_capacity(volumes.len());
+	for volume in volumes {
+		let path = dir.join(format!("{}.html", sanitize_file_name(&volume.title)));
+		if fs::write(&path, render(ln, &volume, options)).is_err() {
+			return write_error(&path);
+		}
+		paths.push(path);
+	}
+
+	Ok(paths)
+}
+
+pub fn render(ln: &Lightnovel, volume: &Volume, options: &HtmlOptions) -> String {
+	let details = ln.get_details();
+
+	let mut title_page = String::new();
+	if let Some(cover) = &options.cover {
+		let _ = writeln!(
+			title_page,
+			"\t\t\t<img class=\"cover\" src=\"data:{};base64,{}\" alt=\"Cover\">",
+			escape_xml(&cover.media_type),
+			base64::encode(&cover.data)
+		);
+	}
+	let _ = writeln!(title_page, "\t\t\t<h1>{}</h1>", escape_xml(&volume.title));
+	if !details.authors.is_empty() {
+		let _ = writeln!(title_page, "\t\t\t<p class=\"meta\">{}</p>", escape_xml(&details.authors.join(", ")));
+	}
+	if !details.genres.is_empty() {
+		let _ = writeln!(title_page, "\t\t\t<p class=\"meta\">{}</p>", escape_xml(&details.genres.join(", ")));
+	}
+	if let Some(status) = &details.status {
+		let _ = writeln!(title_page, "\t\t\t<p class=\"meta\">{}</p>", escape_xml(status));
+	}
+	for paragraph in &details.synopsis {
+		let _ = writeln!(title_page, "\t\t\t<p class=\"synopsis\">{}</p>", escape_xml(paragraph));
+	}
+
+	let mut toc = String::new();
+	let mut content = String::new();
+	for chapter in &volume.chapters {
+		let id = format!("chapter-{}", chapter.get_chapter_number());
+		let title = escape_xml(chapter.get_title());
+
+		let _ = writeln!(toc, "\t\t\t\t<li><a href=\"#{}\">{}</a></li>", id, title);
+
+		let _ = writeln!(content, "\t\t\t<section class=\"chapter\" id=\"{}\">", id);
+		let _ = writeln!(content, "\t\t\t\t<h2><a href=\"#{}\">{}</a></h2>", id, title);
+		for paragraph in chapter.iter() {
+			let lines = paragraph.split('\n').map(escape_xml).collect::<Vec<_>>();
+			let _ = writeln!(content, "\t\t\t\t<p>{}</p>", lines.join("<br>"));
+		}
+		let _ = writeln!(content, "\t\t\t\t<p class=\"back\"><a href=\"#toc\">Contents</a></p>");
+		let _ = writeln!(content, "\t\t\t</section>");
+	}
+
+	format!(
+		r#"<!DOCTYPE html>
+<html lang="{}">
+	<head>
+		<meta charset="utf-8">
+		<meta name="viewport" content="width=device-width, initial-scale=1">
+		<title>{}</title>
+		<style>
+{}		</style>
+	</head>
+	<body>
+		<button id="theme" type="button">Theme</button>
+		<header class="title-page">
+{}		</header>
+		<nav id="toc">
+			<h2>Contents</h2>
+			<ol>
+{}			</ol>
+		</nav>
+		<main>
+{}		</main>
+		<script>
+{}
+		</script>
+	</body>
+</html>
+"#,
+		escape_xml(&options.language),
+		escape_xml(&volume.title),
+		options.css.as_deref().unwrap_or(DEFAULT_CSS),
+		title_page,
+		toc,
+		content,
+		THEME_SCRIPT
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn render_escapes_the_text_and_links_the_chapters() {
+		let ln = Lightnovel::new("Tom & Jerry".to_string(), "/novel/x".to_string());
+		let mut chapter = LightnovelChapter::new("<One>".to_string(), "/novel/x/chapter-1".to_string(), 1);
+		chapter.push("first\nsecond".to_string());
+		let chapters = [chapter];
+		let volume = VolumeRule::Single.split(&ln, &chapters).remove(0);
+		let options = HtmlOptions {
+			cover: Some(Cover::new(b"png".to_vec(), "image/png".to_string())),
+			..Default::default()
+		};
+
+		let html = render(&ln, &volume, &options);
+		assert!(html.contains("<title>Tom &amp; Jerry</title>"));
+		assert!(html.contains("<li><a href=\"#chapter-1\">&lt;One&gt;</a></li>"));
+		assert!(html.contains("<section class=\"chapter\" id=\"chapter-1\">"));
+		assert!(html.contains("<p>first<br>second</p>"));
+		assert!(html.contains("src=\"data:image/png;base64,cG5n\""));
+	}
+}
@@ -0,0 +1,246 @@
+//! JSON and NDJSON export of a lightnovel
+//!
+//! Both formats carry `"schema": "ln-scraper"` and a `"version"` which is raised whenever a field is
+//! removed or changes its meaning, new fields can be added without raising it.
+//!
+//! # JSON (version 1)
+//!
+//! One document per lightnovel:
+//!
+//! ```json
+//! {
+//!   "schema": "ln-scraper",
+//!   "version": 1,
+//!   "novel": {
+//!     "title": "...",
+//!     "url": "https://...",
+//!     "authors": ["..."],
+//!     "genres": ["..."],
+//!     "status": "Completed",
+//!     "synopsis": ["..."],
+//!     "cover": "https://..."
+//!   },
+//!   "chapters": [
+//!     { "number": 1, "title": "...", "url": "https://...", "paragraphs": ["..."] }
+//!   ]
+//! }
+//! ```
+//!
+//! `status` and `cover` are `null` if the site doesn't have them, `chapters` is sorted by `number`.
+//!
+//! # NDJSON (version 1)
+//!
+//! One JSON object per line so long lightnovels can be streamed. The first line is the novel, every
+//! following line is a chapter, both are told apart by their `"type"`. `chapter_count` is told before
+//! the chapters are written, a chapter which turns out to have no text is left out:
+//!
+//! ```json
+//! {"type":"novel","schema":"ln-scraper","version":1,"chapter_count":1,"novel":{"title":"...",...}}
+//! {"type":"chapter","number":1,"title":"...","url":"https://...","paragraphs":["..."]}
+//! ```
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, Result};
+use serde::Serialize;
+
+use super::{absolute_url, sanitize_file_name, write_error};
+use crate::err::ExportError;
+use crate::{Lightnovel, LightnovelChapter};
+
+pub const SCHEMA: &str = "ln-scraper";
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonFormat {
+	Json,
+	Ndjson,
+}
+
+#[derive(Serialize)]
+struct NovelRecord<'a> {
+	title: &'a str,
+	url: String,
+	authors: &'a [String],
+	genres: &'a [String],
+	status: Option<&'a str>,
+	synopsis: &'a [String],
+	cover: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ChapterRecord<'a> {
+	number: usize,
+	title: &'a str,
+	url: &'a str,
+	paragraphs: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+	schema: &'static str,
+	version: u32,
+	novel: NovelRecord<'a>,
+	chapters: Vec<ChapterRecord<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Line<'a> {
+	Novel {
+		schema: &'static str,
+		version: u32,
+		chapter_count: usize,
+		novel: NovelRecord<'a>,
+	},
+	Chapter(ChapterRecord<'a>),
+}
+
+/// Writes the lightnovel into the directory and returns the path of the written file
+pub fn export(ln: &Lightnovel, chapters: &[LightnovelChapter], format: JsonFormat, dir: &Path) -> Result<Vec<PathBuf>, ExportError> {
+	if chapters.is_empty() {
+		return Err(Report::new(ExportError::NoChapters));
+	}
+
+	let (path, file) = create(ln, format, dir)?;
+
+	let written = match format {
+		JsonFormat::Json => write_json(ln, chapters, file),
+		JsonFormat::Ndjson => write_ndjson(ln, chapters.len(), chapters, file),
+	};
+	if written.is_err() {
+		return write_error(&path);
+	}
+
+	Ok(vec![path])
+}
+
+/// Creates the file of the lightnovel in the directory, so a `NdjsonWriter` can stream into it
+pub fn create(ln: &Lightnovel, format: JsonFormat, dir: &Path) -> Result<(PathBuf, BufWriter<File>), ExportError> {
+	if fs::create_dir_all(dir).is_err() {
+		return write_error(dir);
+	}
+
+	let extension = match format {
+		JsonFormat::Json => "json",
+		JsonFormat::Ndjson => "ndjson",
+	};
+	let path = dir.join(format!("{}.{}", sanitize_file_name(ln.get_title()), extension));
+
+	match File::create(&path) {
+		Ok(file) => Ok((path, BufWriter::new(file))),
+		Err(_) => write_error(&path),
+	}
+}
+
+pub fn write_json<W: Write>(ln: &Lightnovel, chapters: &[LightnovelChapter], mut writer: W) -> std::io::Result<()> {
+	let mut chapters = chapters.iter().map(chapter_record).collect::<Vec<_>>();
+	chapters.sort_by_key(|chapter| chapter.number);
+
+	let document = Document {
+		schema: SCHEMA,
+		version: SCHEMA_VERSION,
+		novel: novel_record(ln),
+		chapters,
+	};
+
+	serde_json::to_writer_pretty(&mut writer, &document)?;
+	writeln!(writer)?;
+	writer.flush()
+}
+
+/// Streams the lightnovel as NDJSON, the chapters are written in the order they come in
+pub fn write_ndjson<'a, W: Write, I: IntoIterator<Item = &'a LightnovelChapter>>(
+	ln: &Lightnovel, chapter_count: usize, chapters: I, writer: W,
+) -> std::io::Result<()> {
+	let mut writer = NdjsonWriter::new(ln, chapter_count, writer)?;
+	for chapter in chapters {
+		writer.write_chapter(chapter)?;
+	}
+	writer.finish()
+}
+
+/// Writes NDJSON one chapter at a time, so the chapters don't have to be in memory at the same time
+pub struct NdjsonWriter<W: Write> {
+	writer: W,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+	/// Writes the line of the lightnovel
+	pub fn new(ln: &Lightnovel, chapter_count: usize, mut writer: W) -> std::io::Result<Self> {
+		let header = Line::Novel {
+			schema: SCHEMA,
+			version: SCHEMA_VERSION,
+			chapter_count,
+			novel: novel_record(ln),
+		};
+		serde_json::to_writer(&mut writer, &header)?;
+		writeln!(writer)?;
+
+		Ok(Self { writer })
+	}
+
+	pub fn write_chapter(&mut self, chapter: &LightnovelChapter) -> std::io::Result<()> {
+		serde_json::to_writer(&mut self.writer, &Line::Chapter(chapter_record(chapter)))?;
+		writeln!(self.writer)
+	}
+
+	pub fn finish(mut self) -> std::io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+fn novel_record(ln: &Lightnovel) -> NovelRecord<'_> {
+	let details = ln.get_details();
+
+	NovelRecord {
+		title: ln.get_title(),
+		url: absolute_url(ln.get_url()),
+		authors: &details.authors,
+		genres: &details.genres,
+		status: details.status.as_deref(),
+		synopsis: &details.synopsis,
+		cover: details.cover.as_deref(),
+	}
+}
+
+fn chapter_record(chapter: &LightnovelChapter) -> ChapterRecord<'_> {
+	ChapterRecord {
+		number: chapter.get_chapter_number(),
+		title: chapter.get_title(),
+		url: chapter.get_url(),
+		paragraphs: &chapter[..],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ndjson_has_the_novel_and_then_one_line_per_chapter() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let mut writer = NdjsonWriter::new(&ln, 2, Vec::new()).unwrap();
+		for number in 1..=2 {
+			let mut chapter = LightnovelChapter::new(format!("Chapter {}", number), format!("/novel/x/chapter-{}", number), number);
+			chapter.push(format!("Text {}", number));
+			writer.write_chapter(&chapter).unwrap();
+		}
+
+		let lines = String::from_utf8(writer.writer).unwrap();
+		let lines = lines
+			.lines()
+			.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+			.collect::<Vec<_>>();
+
+		assert_eq!(lines.len(), 3);
+		assert_eq!(lines[0]["type"], "novel");
+		assert_eq!(lines[0]["schema"], SCHEMA);
+		assert_eq!(lines[0]["chapter_count"], 2);
+		assert_eq!(lines[2]["type"], "chapter");
+		assert_eq!(lines[2]["number"], 2);
+		assert_eq!(lines[2]["paragraphs"][0], "Text 2");
+	}
+}
@@ -0,0 +1,426 @@
+pub mod calibre;
+pub mod epub;
+pub mod fb2;
+pub mod html;
+pub mod json;
+pub mod pdf;
+pub mod text;
+
+use std::cmp::max;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use error_stack::{Report, Result, ResultExt};
+
+use super::{Lightnovel, LightnovelChapter};
+use crate::cfg::{client, site};
+use crate::err::{ExportError, SurfError};
+
+/// A cover image of a lightnovel
+#[derive(Debug, Clone)]
+pub struct Cover {
+	pub data: Vec<u8>,
+	pub media_type: String,
+}
+
+impl Cover {
+	pub fn new(data: Vec<u8>, media_type: String) -> Self {
+		Self { data, media_type }
+	}
+
+	pub fn from_path(path: &Path) -> Result<Self, ExportError> {
+		let data = match fs::read(path) {
+			Ok(data) => data,
+			Err(_) => {
+				let msg = format!("There was a problem with reading the cover: {}", path.display());
+				let report = Report::new(ExportError::ReadError(msg.clone()));
+				return Err(report.attach_printable(msg));
+			}
+		};
+
+		let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+		Ok(Self::from_extension(data, extension))
+	}
+
+	pub(crate) fn from_extension(data: Vec<u8>, extension: &str) -> Self {
+		let media_type = match extension.to_lowercase().as_str() {
+			"png" => "image/png",
+			"gif" => "image/gif",
+			"webp" => "image/webp",
+			"svg" => "image/svg+xml",
+			_ => "image/jpeg",
+		};
+
+		Self::new(data, media_type.to_string())
+	}
+
+	pub async fn fetch(url: &str) -> Result<Self, ExportError> {
+		let client = client().change_context(SurfError::ClientCreationError.into())?;
+
+		let mut res = match client.get(url).send().await {
+			Ok(res) => res,
+			Err(_) => {
+				let msg = format!("There was a problem while with sending the requet to: {}", url);
+				let report = Report::new(SurfError::RequestError(msg.clone()).into());
+				return Err(report.attach_printable(msg));
+			}
+		};
+
+		if !res.status().is_success() {
+			let msg = format!("The cover {} answered with: {}", url, res.status());
+			let report = Report::new(SurfError::RequestError(msg.clone()).into());
+			return Err(report.attach_printable(msg));
+		}
+
+		let media_type = res.content_type().map(|mime| mime.essence().to_string());
+
+		let data = match res.body_bytes().await {
+			Ok(data) => data,
+			Err(_) => {
+				let msg = format!("There was a problem with getting the body from: {}", url);
+				let report = Report::new(SurfError::BodyParseError(msg.clone()).into());
+				return Err(report.attach_printable(msg));
+			}
+		};
+
+		// An error page of the site or its proxy mustn't end up as the cover
+		match media_type.filter(|media_type| media_type.starts_with("image/")) {
+			Some(media_type) => Ok(Self::new(data, media_type)),
+			None => match image_type(&data) {
+				Some(media_type) => Ok(Self::new(data, media_type.to_string())),
+				None => {
+					let msg = format!("The cover is not an image: {}", url);
+					let report = Report::new(SurfError::BodyParseError(msg.clone()).into());
+					Err(report.attach_printable(msg))
+				}
+			},
+		}
+	}
+
+	pub fn extension(&self) -> &str {
+		match self.media_type.as_str() {
+			"image/png" => "png",
+			"image/gif" => "gif",
+			"image/webp" => "webp",
+			"image/svg+xml" => "svg",
+			_ => "jpg",
+		}
+	}
+}
+
+/// The media type of an image by its first bytes
+fn image_type(data: &[u8]) -> Option<&'static str> {
+	if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+		Some("image/png")
+	} else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+		Some("image/jpeg")
+	} else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+		Some("image/gif")
+	} else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+		Some("image/webp")
+	} else {
+		None
+	}
+}
+
+/// How the chapters of a lightnovel are split into volumes
+#[derive(Debug, Clone, Default)]
+pub enum VolumeRule {
+	#[default]
+	Single,
+	ChapterCount(usize),
+	/// A chapter is in the first range it is in, the chapters outside of every range make the last volume
+	ChapterRanges(Vec<RangeInclusive<usize>>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Volume<'a> {
+	pub number: usize,
+	pub title: String,
+	pub chapters: Vec<&'a LightnovelChapter>,
+}
+
+impl VolumeRule {
+	/// Splits the chapters into volumes
+	///
+	/// Only `Single` gives the volume the title of the lightnovel, the other rules number their volumes even when
+	/// the chapters fit into one, so the file of a volume keeps its name once the lightnovel grows.
+	pub fn split<'a>(&self, ln: &Lightnovel, chapters: &'a [LightnovelChapter]) -> Vec<Volume<'a>> {
+		let groups = match self {
+			Self::Single => vec![chapters.iter().collect::<Vec<_>>()],
+			Self::ChapterCount(count) => chapters.chunks(max(*count, 1)).map(|chunk| chunk.iter().collect()).collect(),
+			Self::ChapterRanges(ranges) => {
+				let mut groups = vec![Vec::new(); ranges.len() + 1];
+				for chapter in chapters {
+					let i = ranges
+						.iter()
+						.position(|range| range.contains(&chapter.get_chapter_number()))
+						.unwrap_or(ranges.len());
+					groups[i].push(chapter);
+				}
+				groups
+			}
+		};
+
+		groups
+			.into_iter()
+			.filter(|group| !group.is_empty())
+			.enumerate()
+			.map(|(i, chapters)| Volume {
+				number: i + 1,
+				title: if matches!(self, Self::Single) {
+					ln.get_title().clone()
+				} else {
+					format!("{} - Volume {}", ln.get_title(), i + 1)
+				},
+				chapters,
+			})
+			.collect()
+	}
+}
+
+/// Replaces the characters that are illegal or awkward in file names on common filesystems
+pub fn sanitize_file_name(name: &str) -> String {
+	let name = name
+		.chars()
+		.map(|c| match c {
+			'/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+			c if c.is_control() => '_',
+			c => c,
+		})
+		.collect::<String>();
+
+	let name = name.trim().trim_end_matches('.').trim();
+
+	let reserved = ["CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "LPT1", "LPT2", "LPT3"];
+	let stem = name.split('.').next().unwrap_or_default();
+	let name = if reserved.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+		format!("_{}", name)
+	} else {
+		name.to_string()
+	};
+
+	if name.is_empty() {
+		"_".to_string()
+	} else {
+		name.chars().take(200).collect()
+	}
+}
+
+/// Escapes text for XML and drops the control characters XML 1.0 doesn't allow
+pub(crate) fn escape_xml(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			'\t' | '\n' | '\r' => escaped.push(c),
+			c if c.is_control() || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+			c => escaped.push(c),
+		}
+	}
+
+	escaped
+}
+
+/// Adds the site to the links of the lightnovel which start with `/`
+pub fn absolute_url(url: &str) -> String {
+	if url.starts_with('/') {
+		format!("{}{}", site(), url)
+	} else {
+		url.to_string()
+	}
+}
+
+/// The current time in UTC like `2022-10-01T12:00:00Z`
+pub(crate) fn timestamp() -> String {
+	let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default() as i64;
+	let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z - era * 146097;
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+		year,
+		month,
+		day,
+		secs / 3600,
+		secs % 3600 / 60,
+		secs % 60
+	)
+}
+
+pub(crate) fn read_error<T>(path: &Path) -> Result<T, ExportError> {
+	let msg = format!("There was a problem with reading the export: {}", path.display());
+	let report = Report::new(ExportError::ReadError(msg.clone()));
+	Err(report.attach_printable(msg))
+}
+
+pub(crate) fn write_error<T>(path: &Path) -> Result<T, ExportError> {
+	let msg = format!("There was a problem with writing the export: {}", path.display());
+	let report = Report::new(ExportError::WriteError(msg.clone()));
+	Err(report.attach_printable(msg))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chapters(numbers: RangeInclusive<usize>) -> Vec<LightnovelChapter> {
+		numbers
+			.map(|n| LightnovelChapter::new(format!("Chapter {}", n), format!("/novel/x/chapter-{}", n), n))
+			.collect()
+	}
+
+	fn numbers(volume: &Volume) -> Vec<usize> {
+		volume.chapters.iter().map(|c| c.get_chapter_number()).collect()
+	}
+
+	#[test]
+	fn sanitize_file_name_replaces_illegal_characters() {
+		assert_eq!(sanitize_file_name("Re:Zero / Part 1?"), "Re_Zero _ Part 1_");
+		assert_eq!(sanitize_file_name("a\tb<c>|\"d\""), "a_b_c___d_");
+	}
+
+	#[test]
+	fn sanitize_file_name_trims_dots_and_spaces() {
+		assert_eq!(sanitize_file_name("  The End...  "), "The End");
+		assert_eq!(sanitize_file_name(" . "), "_");
+		assert_eq!(sanitize_file_name(""), "_");
+	}
+
+	#[test]
+	fn sanitize_file_name_escapes_reserved_names() {
+		assert_eq!(sanitize_file_name("con"), "_con");
+		assert_eq!(sanitize_file_name("NUL.txt"), "_NUL.txt");
+		assert_eq!(sanitize_file_name("Console"), "Console");
+	}
+
+	#[test]
+	fn sanitize_file_name_cuts_long_names() {
+		assert_eq!(sanitize_file_name(&"é".repeat(300)).chars().count(), 200);
+	}
+
+	#[test]
+	fn escape_xml_escapes_markup() {
+		assert_eq!(
+			escape_xml(r#"<a href="x">Tom & 'Jerry'</a>"#),
+			"&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;"
+		);
+	}
+
+	#[test]
+	fn escape_xml_drops_illegal_characters() {
+		assert_eq!(escape_xml("a\u{0}b\u{1b}c\u{FFFE}d"), "abcd");
+		assert_eq!(escape_xml("line\n\tnext"), "line\n\tnext");
+	}
+
+	#[test]
+	fn split_single_is_one_volume_with_the_title() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let chapters = chapters(1..=5);
+		let volumes = VolumeRule::Single.split(&ln, &chapters);
+
+		assert_eq!(volumes.len(), 1);
+		assert_eq!(volumes[0].title, "Novel");
+		assert_eq!(numbers(&volumes[0]), vec![1, 2, 3, 4, 5]);
+	}
+
+	#[test]
+	fn split_by_chapter_count() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let chapters = chapters(1..=5);
+		let volumes = VolumeRule::ChapterCount(2).split(&ln, &chapters);
+
+		assert_eq!(volumes.iter().map(numbers).collect::<Vec<_>>(), vec![vec![1, 2], vec![3, 4], vec![5]]);
+		assert_eq!(volumes[2].title, "Novel - Volume 3");
+		assert_eq!(VolumeRule::ChapterCount(0).split(&ln, &chapters).len(), 5);
+	}
+
+	#[test]
+	fn split_by_ranges_neither_duplicates_nor_drops_chapters() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let chapters = chapters(1..=10);
+		let volumes = VolumeRule::ChapterRanges(vec![1..=4, 3..=6, 9..=20]).split(&ln, &chapters);
+
+		assert_eq!(
+			volumes.iter().map(numbers).collect::<Vec<_>>(),
+			vec![vec![1, 2, 3, 4], vec![5, 6], vec![9, 10], vec![7, 8]]
+		);
+		assert_eq!(volumes.iter().map(|v| v.number).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+	}
+
+	#[test]
+	fn split_by_ranges_skips_empty_volumes() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let chapters = chapters(1..=3);
+		let volumes = VolumeRule::ChapterRanges(vec![1..=3, 50..=60]).split(&ln, &chapters);
+
+		assert_eq!(volumes.len(), 1);
+		assert_eq!(volumes[0].title, "Novel - Volume 1");
+	}
+
+	/// Answers the requests on a local port with the response and returns the url
+	///
+	/// The redirect middleware sends every request twice, so it answers more than one.
+	fn serve(response: &'static [u8]) -> String {
+		use std::io::{Read, Write};
+		use std::net::TcpListener;
+
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/cover.jpg", listener.local_addr().unwrap());
+		std::thread::spawn(move || {
+			for mut stream in listener.incoming().flatten() {
+				let mut request = Vec::new();
+				let mut buf = [0; 1024];
+				while !request.ends_with(b"\r\n\r\n") {
+					match stream.read(&mut buf) {
+						Ok(0) | Err(_) => break,
+						Ok(n) => request.extend_from_slice(&buf[..n]),
+					}
+				}
+				let _ = stream.write_all(response);
+			}
+		});
+		url
+	}
+
+	#[test]
+	fn fetch_rejects_error_pages() {
+		let url = serve(b"HTTP/1.0 404 Not Found\r\nContent-Type: image/jpeg\r\nContent-Length: 3\r\n\r\nnot");
+		assert!(async_std::task::block_on(Cover::fetch(&url)).is_err());
+
+		let url = serve(b"HTTP/1.0 200 OK\r\nContent-Type: text/html\r\nContent-Length: 15\r\n\r\n<!DOCTYPE html>");
+		assert!(async_std::task::block_on(Cover::fetch(&url)).is_err());
+	}
+
+	#[test]
+	fn fetch_takes_images() {
+		let url = serve(b"HTTP/1.0 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 8\r\n\r\n\x89PNG\r\n\x1a\n");
+		let cover = async_std::task::block_on(Cover::fetch(&url)).unwrap();
+		assert_eq!(cover.media_type, "image/png");
+		assert_eq!(cover.extension(), "png");
+	}
+
+	#[test]
+	fn image_type_by_the_first_bytes() {
+		assert_eq!(image_type(b"\x89PNG\r\n\x1a\n...."), Some("image/png"));
+		assert_eq!(image_type(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("image/jpeg"));
+		assert_eq!(image_type(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+		assert_eq!(image_type(b"<!DOCTYPE html>"), None);
+	}
+}
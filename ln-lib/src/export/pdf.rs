@@ -0,0 +1,730 @@
+//! PDF export of a lightnovel
+//!
+//! The PDF is written by hand and only uses the standard fonts every PDF reader ships, so no font files
+//! or external tools are needed. Those fonts only cover Windows-1252, other characters are printed as `?`.
+//! Every volume gets a title page, a table of contents with links and an outline entry for every chapter.
+//! JPEG covers are embedded as they are, other image types are left out.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use error_stack::{Report, Result};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use super::{sanitize_file_name, timestamp, write_error, Cover, Volume, VolumeRule};
+use crate::err::ExportError;
+use crate::{Lightnovel, LightnovelChapter};
+
+const LINE_HEIGHT: f32 = 1.4;
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+/// Bold glyphs are measured with the regular widths and this factor
+const BOLD_WIDTH: f32 = 1.1;
+
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 95] = [
+	278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+	556, 556, 556, 556, 556, 556, 556, 556, 556, 556,
+	278, 278, 584, 584, 584, 556, 1015,
+	667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611,
+	278, 278, 278, 469, 556, 333,
+	556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500,
+	334, 260, 334, 584,
+];
+
+#[rustfmt::skip]
+const TIMES_WIDTHS: [u16; 95] = [
+	250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278,
+	500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+	278, 278, 564, 564, 564, 444, 921,
+	722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611,
+	333, 278, 333, 469, 500, 333,
+	444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444,
+	480, 200, 480, 541,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+	#[default]
+	A4,
+	A5,
+	Letter,
+	/// The page of a 6" e-reader
+	EReader,
+}
+
+impl PageSize {
+	/// The width and height in points
+	pub fn dimensions(&self) -> (f32, f32) {
+		match self {
+			Self::A4 => (595.28, 841.89),
+			Self::A5 => (419.53, 595.28),
+			Self::Letter => (612.0, 792.0),
+			Self::EReader => (259.2, 345.6),
+		}
+	}
+}
+
+impl FromStr for PageSize {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.trim().to_lowercase().as_str() {
+			"a4" => Ok(Self::A4),
+			"a5" => Ok(Self::A5),
+			"letter" => Ok(Self::Letter),
+			"6in" | "6\"" | "ereader" | "e-reader" => Ok(Self::EReader),
+			_ => Err(format!("'{}' is not a page size (a4, a5, letter or 6in)", s)),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PdfFont {
+	#[default]
+	Times,
+	Helvetica,
+	Courier,
+}
+
+impl PdfFont {
+	fn regular(&self) -> &str {
+		match self {
+			Self::Times => "Times-Roman",
+			Self::Helvetica => "Helvetica",
+			Self::Courier => "Courier",
+		}
+	}
+
+	fn bold(&self) -> &str {
+		match self {
+			Self::Times => "Times-Bold",
+			Self::Helvetica => "Helvetica-Bold",
+			Self::Courier => "Courier-Bold",
+		}
+	}
+
+	/// The width of a Windows-1252 character in thousandths of the font size
+	fn width(&self, c: u8) -> u16 {
+		let widths = match self {
+			Self::Times => &TIMES_WIDTHS,
+			Self::Helvetica => &HELVETICA_WIDTHS,
+			Self::Courier => return 600,
+		};
+
+		match (c, self) {
+			(32..=126, _) => widths[(c - 32) as usize],
+			(0x85 | 0x97, _) => 1000,
+			(0x91 | 0x92, Self::Times) => 333,
+			(0x91 | 0x92, _) => 222,
+			(0x93 | 0x94, Self::Times) => 444,
+			(0x93 | 0x94, _) => 333,
+			(0xC0..=0xDE, _) => 722,
+			(_, Self::Times) => 500,
+			(_, _) => 556,
+		}
+	}
+}
+
+impl FromStr for PdfFont {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.trim().to_lowercase().as_str() {
+			"times" | "serif" => Ok(Self::Times),
+			"helvetica" | "sans" | "sans-serif" => Ok(Self::Helvetica),
+			"courier" | "mono" | "monospace" => Ok(Self::Courier),
+			_ => Err(format!("'{}' is not a font (times, helvetica or courier)", s)),
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+	pub page_size: PageSize,
+	/// The margin on every side in millimetres
+	pub margin: f32,
+	pub font: PdfFont,
+	/// The size of the text in points, headings are scaled from it
+	pub font_size: f32,
+	pub cover: Option<Cover>,
+	pub volumes: VolumeRule,
+}
+
+impl Default for PdfOptions {
+	fn default() -> Self {
+		Self {
+			page_size: PageSize::default(),
+			margin: 20.0,
+			font: PdfFont::default(),
+			font_size: 11.0,
+			cover: None,
+			volumes: VolumeRule::default(),
+		}
+	}
+}
+
+/// Writes one PDF per volume into the directory and returns the paths of the written files
+pub fn export(ln: &Lightnovel, chapters: &[LightnovelChapter], options: &PdfOptions, dir: &Path) -> Result<Vec<PathBuf>, ExportError> {
+	let volumes = options.volumes.split(ln, chapters);
+	if volumes.is_empty() {
+		return Err(Report::new(ExportError::NoChapters));
+	}
+
+	if fs::create_dir_all(dir).is_err() {
+		return write_error(dir);
+	}
+
+	let mut paths = Vec::with_capacity(volumes.len());
+	for volume in volumes {
+		let path = dir.join(format!("{}.pdf", sanitize_file_name(&volume.title)));
+		if fs::write(&path, render(ln, &volume, options)).is_err() {
+			return write_error(&path);
+		}
+		paths.push(path);
+	}
+
+	Ok(paths)
+}
+
+pub fn render(ln: &Lightnovel, volume: &Volume, options: &PdfOptions) -> Vec<u8> {
+	let details = ln.get_details();
+	let size = options.font_size.max(4.0);
+	let line_height = size * LINE_HEIGHT;
+	let cover = options.cover.as_ref().and_then(|cover| jpeg_info(&cover.data).map(|info| (cover, info)));
+
+	let mut front = Layout::new(options);
+	if let Some((_, (width, height, _))) = cover {
+		front.add_page();
+		front.image(width as f32, height as f32);
+	}
+	front.add_page();
+	front.space(front.body_height() / 5.0);
+	front.paragraph(&volume.title, size * 2.0, true, true);
+	front.space(line_height);
+	if !details.authors.is_empty() {
+		front.paragraph(&details.authors.join(", "), size * 1.2, false, true);
+	}
+	front.space(line_height);
+	if !details.genres.is_empty() {
+		front.paragraph(&details.genres.join(", "), size, false, true);
+	}
+	if let Some(status) = &details.status {
+		front.paragraph(status, size, false, true);
+	}
+	front.space(line_height * 2.0);
+	for paragraph in &details.synopsis {
+		front.paragraph(paragraph, size, false, false);
+		front.space(line_height / 2.0);
+	}
+
+	let mut body = Layout::new(options);
+	let mut targets = Vec::with_capacity(volume.chapters.len());
+	for chapter in &volume.chapters {
+		body.add_page();
+		targets.push((body.pages.len() - 1, body.y));
+		body.paragraph(chapter.get_title(), size * 1.5, true, false);
+		body.space(line_height);
+		for paragraph in chapter.iter() {
+			for line in paragraph.split('\n') {
+				body.paragraph(line, size, false, false);
+			}
+			body.space(line_height / 2.0);
+		}
+	}
+
+	// The page numbers in the table of contents depend on its own length, so it is laid out until that settles
+	let mut toc_pages = 1;
+	let toc = loop {
+		let offset = front.pages.len() + toc_pages;
+		let mut toc = Layout::new(options);
+		toc.add_page();
+		toc.paragraph("Contents", size * 1.5, true, false);
+		toc.space(line_height);
+		for (chapter, (page, _)) in volume.chapters.iter().zip(&targets) {
+			toc.entry(chapter.get_title(), offset + page, size);
+		}
+
+		if toc.pages.len() == toc_pages {
+			break toc;
+		}
+		toc_pages = toc.pages.len();
+	};
+
+	let offset = front.pages.len() + toc.pages.len();
+	let numbered = front.pages.len();
+	let (width, height, margin) = (front.width, front.height, front.margin);
+
+	let mut pages = front.pages;
+	pages.extend(toc.pages);
+	pages.extend(body.pages);
+	for (i, page) in pages.iter_mut().enumerate().skip(numbered) {
+		let label = encode(&(i + 1).to_string());
+		let x = (width - text_width(options.font, &label, size * 0.8, false)) / 2.0;
+		page.text(&label, size * 0.8, false, x, margin / 2.0);
+	}
+
+	let mut pdf = Pdf::default();
+	let catalog = pdf.reserve();
+	let pages_id = pdf.reserve();
+	let outlines = pdf.reserve();
+
+	let regular = pdf.add(format!(
+		"<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+		options.font.regular()
+	));
+	let bold = pdf.add(format!(
+		"<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+		options.font.bold()
+	));
+	let image = cover.map(|(cover, (width, height, components))| {
+		let color_space = match components {
+			1 => "DeviceGray",
+			4 => "DeviceCMYK",
+			_ => "DeviceRGB",
+		};
+		let dict = format!(
+			"/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Filter /DCTDecode",
+			width, height, color_space
+		);
+		pdf.add(stream(&dict, &cover.data))
+	});
+	let resources = pdf.add(format!(
+		"<< /Font << /F1 {} 0 R /F2 {} 0 R >>{} >>",
+		regular,
+		bold,
+		image.map(|image| format!(" /XObject << /Im1 {} 0 R >>", image)).unwrap_or_default()
+	));
+
+	let page_ids = pages.iter().map(|_| pdf.reserve()).collect::<Vec<_>>();
+	for (page, id) in pages.iter().zip(&page_ids) {
+		let contents = pdf.add(compressed_stream(page.content.as_bytes()));
+		let annots = page
+			.links
+			.iter()
+			.map(|(rect, target)| {
+				format!(
+					"<< /Type /Annot /Subtype /Link /Rect [{:.2} {:.2} {:.2} {:.2}] /Border [0 0 0] /Dest [{} 0 R /XYZ null null null] >>",
+					rect[0], rect[1], rect[2], rect[3], page_ids[*target]
+				)
+			})
+			.collect::<Vec<_>>();
+		pdf.set(
+			*id,
+			format!(
+				"<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources {} 0 R /Contents {} 0 R /Annots [{}] >>",
+				pages_id,
+				width,
+				height,
+				resources,
+				contents,
+				annots.join(" ")
+			),
+		);
+	}
+	pdf.set(
+		pages_id,
+		format!(
+			"<< /Type /Pages /Kids [{}] /Count {} >>",
+			page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+			page_ids.len()
+		),
+	);
+
+	let items = targets.iter().map(|_| pdf.reserve()).collect::<Vec<_>>();
+	for (i, (chapter, (page, top))) in volume.chapters.iter().zip(&targets).enumerate() {
+		let mut item = format!(
+			"<< /Title {} /Parent {} 0 R /Dest [{} 0 R /XYZ null {:.2} null]",
+			text_string(chapter.get_title()),
+			outlines,
+			page_ids[offset + page],
+			top
+		);
+		if i > 0 {
+			let _ = write!(item, " /Prev {} 0 R", items[i - 1]);
+		}
+		if let Some(next) = items.get(i + 1) {
+			let _ = write!(item, " /Next {} 0 R", next);
+		}
+		item.push_str(" >>");
+		pdf.set(items[i], item);
+	}
+	match (items.first(), items.last()) {
+		(Some(first), Some(last)) => pdf.set(
+			outlines,
+			format!("<< /Type /Outlines /First {} 0 R /Last {} 0 R /Count {} >>", first, last, items.len()),
+		),
+		_ => pdf.set(outlines, "<< /Type /Outlines /Count 0 >>".to_string()),
+	}
+
+	let date = timestamp().chars().filter(char::is_ascii_digit).collect::<String>();
+	let info = pdf.add(format!(
+		"<< /Title {} /Author {} /Producer (ln-scraper) /CreationDate (D:{}Z) >>",
+		text_string(&volume.title),
+		text_string(&details.authors.join(", ")),
+		date
+	));
+	pdf.set(
+		catalog,
+		format!(
+			"<< /Type /Catalog /Pages {} 0 R /Outlines {} 0 R /PageMode /UseOutlines >>",
+			pages_id, outlines
+		),
+	);
+
+	pdf.finish(catalog, info)
+}
+
+#[derive(Default)]
+struct Page {
+	content: String,
+	/// The clickable area and the index of the page it leads to
+	links: Vec<([f32; 4], usize)>,
+}
+
+impl Page {
+	fn text(&mut self, text: &[u8], size: f32, bold: bool, x: f32, y: f32) {
+		let _ = writeln!(
+			self.content,
+			"BT /{} {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+			if bold { "F2" } else { "F1" },
+			size,
+			x,
+			y,
+			literal(text)
+		);
+	}
+}
+
+struct Layout<'a> {
+	options: &'a PdfOptions,
+	width: f32,
+	height: f32,
+	margin: f32,
+	pages: Vec<Page>,
+	/// The top of the free space on the current page
+	y: f32,
+}
+
+impl<'a> Layout<'a> {
+	fn new(options: &'a PdfOptions) -> Self {
+		let (width, height) = options.page_size.dimensions();
+		let margin = (options.margin.max(0.0) * POINTS_PER_MM).min(width.min(height) / 4.0);
+
+		Self {
+			options,
+			width,
+			height,
+			margin,
+			pages: Vec::new(),
+			y: height - margin,
+		}
+	}
+
+	fn body_width(&self) -> f32 {
+		self.width - 2.0 * self.margin
+	}
+
+	fn body_height(&self) -> f32 {
+		self.height - 2.0 * self.margin
+	}
+
+	fn add_page(&mut self) {
+		self.pages.push(Page::default());
+		self.y = self.height - self.margin;
+	}
+
+	fn space(&mut self, height: f32) {
+		self.y -= height;
+	}
+
+	/// Moves to the next line and returns its baseline, a new page is started if the line doesn't fit
+	fn next_line(&mut self, size: f32) -> f32 {
+		let line_height = size * LINE_HEIGHT;
+		if self.pages.is_empty() || self.y - line_height < self.margin {
+			self.add_page();
+		}
+
+		self.y -= line_height;
+		self.y + (line_height - size) / 2.0 + size * 0.2
+	}
+
+	fn paragraph(&mut self, text: &str, size: f32, bold: bool, center: bool) {
+		let lines = wrap(self.options.font, &encode(text), size, bold, self.body_width());
+
+		for line in lines {
+			let baseline = self.next_line(size);
+			let x = if center {
+				(self.width - text_width(self.options.font, &line, size, bold)) / 2.0
+			} else {
+				self.margin
+			};
+			self.pages.last_mut().unwrap().text(&line, size, bold, x, baseline);
+		}
+	}
+
+	/// A line of the table of contents with the page number on the right that links to the page
+	fn entry(&mut self, title: &str, page: usize, size: f32) {
+		let font = self.options.font;
+		let label = encode(&(page + 1).to_string());
+		let label_width = text_width(font, &label, size, false);
+		let title = truncate(font, &encode(title), size, self.body_width() - label_width - size * 2.0);
+
+		let baseline = self.next_line(size);
+		let (margin, width, y) = (self.margin, self.width, self.y);
+		let current = self.pages.last_mut().unwrap();
+		current.text(&title, size, false, margin, baseline);
+		current.text(&label, size, false, width - margin - label_width, baseline);
+		current.links.push(([margin, y, width - margin, y + size * LINE_HEIGHT], page));
+	}
+
+	/// Draws the cover as large as the page allows
+	fn image(&mut self, width: f32, height: f32) {
+		let scale = (self.body_width() / width).min(self.body_height() / height);
+		let (width, height) = (width * scale, height * scale);
+		let x = (self.width - width) / 2.0;
+		let y = (self.height - height) / 2.0;
+
+		let _ = writeln!(
+			self.pages.last_mut().unwrap().content,
+			"q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im1 Do Q",
+			width,
+			height,
+			x,
+			y
+		);
+		self.y = self.margin;
+	}
+}
+
+fn text_width(font: PdfFont, text: &[u8], size: f32, bold: bool) -> f32 {
+	let width = text.iter().map(|c| font.width(*c) as f32).sum::<f32>() * size / 1000.0;
+	if bold {
+		width * BOLD_WIDTH
+	} else {
+		width
+	}
+}
+
+/// Breaks the text into lines that fit into the width, words that are too long are split
+fn wrap(font: PdfFont, text: &[u8], size: f32, bold: bool, width: f32) -> Vec<Vec<u8>> {
+	let mut lines = Vec::new();
+	let mut line = Vec::new();
+
+	for word in text.split(|c| *c == b' ').filter(|word| !word.is_empty()) {
+		let mut candidate = line.clone();
+		if !candidate.is_empty() {
+			candidate.push(b' ');
+		}
+		candidate.extend_from_slice(word);
+
+		if text_width(font, &candidate, size, bold) <= width {
+			line = candidate;
+			continue;
+		}
+
+		if !line.is_empty() {
+			lines.push(line);
+		}
+		line = Vec::new();
+		for c in word {
+			line.push(*c);
+			if line.len() > 1 && text_width(font, &line, size, bold) > width {
+				let c = line.pop().unwrap();
+				lines.push(line);
+				line = vec![c];
+			}
+		}
+	}
+
+	if !line.is_empty() {
+		lines.push(line);
+	}
+
+	lines
+}
+
+fn truncate(font: PdfFont, text: &[u8], size: f32, width: f32) -> Vec<u8> {
+	if text_width(font, text, size, false) <= width {
+		return text.to_vec();
+	}
+
+	let mut text = text.to_vec();
+	while !text.is_empty() && text_width(font, &text, size, false) + font.width(0x85) as f32 * size / 1000.0 > width {
+		text.pop();
+	}
+	text.push(0x85);
+	text
+}
+
+/// Converts the text to Windows-1252, the encoding of the standard fonts
+fn encode(text: &str) -> Vec<u8> {
+	text
+		.chars()
+		.filter_map(|c| match c {
+			'\t' => Some(b' '),
+			' '..='~' | '\u{A0}'..='\u{FF}' => Some(c as u8),
+			'€' => Some(0x80),
+			'‚' => Some(0x82),
+			'ƒ' => Some(0x83),
+			'„' => Some(0x84),
+			'…' => Some(0x85),
+			'†' => Some(0x86),
+			'‡' => Some(0x87),
+			'ˆ' => Some(0x88),
+			'‰' => Some(0x89),
+			'Š' => Some(0x8A),
+			'‹' => Some(0x8B),
+			'Œ' => Some(0x8C),
+			'Ž' => Some(0x8E),
+			'‘' => Some(0x91),
+			'’' => Some(0x92),
+			'“' => Some(0x93),
+			'”' => Some(0x94),
+			'•' => Some(0x95),
+			'–' => Some(0x96),
+			'—' => Some(0x97),
+			'˜' => Some(0x98),
+			'™' => Some(0x99),
+			'š' => Some(0x9A),
+			'›' => Some(0x9B),
+			'œ' => Some(0x9C),
+			'ž' => Some(0x9E),
+			'Ÿ' => Some(0x9F),
+			c if c.is_control() => None,
+			_ => Some(b'?'),
+		})
+		.collect()
+}
+
+/// A string for the content stream, everything outside of printable ASCII is escaped
+fn literal(text: &[u8]) -> String {
+	let mut literal = String::with_capacity(text.len());
+
+	for c in text {
+		match c {
+			b'(' | b')' | b'\\' => {
+				literal.push('\\');
+				literal.push(*c as char);
+			}
+			b' '..=b'~' => literal.push(*c as char),
+			_ => {
+				let _ = write!(literal, "\\{:03o}", c);
+			}
+		}
+	}
+
+	literal
+}
+
+/// A string outside of the content streams, like titles in the outline, which can hold any character
+fn text_string(text: &str) -> String {
+	let mut string = String::from("<FEFF");
+
+	for unit in text.encode_utf16() {
+		let _ = write!(string, "{:04X}", unit);
+	}
+
+	string.push('>');
+	string
+}
+
+/// The width, height and number of colour components of a JPEG
+fn jpeg_info(data: &[u8]) -> Option<(u16, u16, u8)> {
+	if !data.starts_with(&[0xFF, 0xD8]) {
+		return None;
+	}
+
+	let mut i = 2;
+	while i + 4 <= data.len() {
+		if data[i] != 0xFF {
+			return None;
+		}
+
+		let marker = data[i + 1];
+		let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+		if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+			let frame = data.get(i + 4..i + 10)?;
+			let height = u16::from_be_bytes([frame[1], frame[2]]);
+			let width = u16::from_be_bytes([frame[3], frame[4]]);
+			return Some((width, height, frame[5]));
+		}
+
+		i += 2 + length;
+	}
+
+	None
+}
+
+fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+	let mut object = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+	object.extend_from_slice(data);
+	object.extend_from_slice(b"\nendstream");
+	object
+}
+
+fn compressed_stream(data: &[u8]) -> Vec<u8> {
+	let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+	match encoder.write_all(data).and_then(|_| encoder.finish()) {
+		Ok(compressed) => stream("/Filter /FlateDecode", &compressed),
+		Err(_) => stream("", data),
+	}
+}
+
+/// The objects of a PDF, an object has the number of its index plus one
+#[derive(Default)]
+struct Pdf {
+	objects: Vec<Vec<u8>>,
+}
+
+impl Pdf {
+	fn reserve(&mut self) -> usize {
+		self.objects.push(Vec::new());
+		self.objects.len()
+	}
+
+	fn set<T: Into<Vec<u8>>>(&mut self, id: usize, object: T) {
+		self.objects[id - 1] = object.into();
+	}
+
+	fn add<T: Into<Vec<u8>>>(&mut self, object: T) -> usize {
+		let id = self.reserve();
+		self.set(id, object);
+		id
+	}
+
+	fn finish(self, root: usize, info: usize) -> Vec<u8> {
+		let mut pdf = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+		let mut offsets = Vec::with_capacity(self.objects.len());
+
+		for (i, object) in self.objects.iter().enumerate() {
+			offsets.push(pdf.len());
+			pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+			pdf.extend_from_slice(object);
+			pdf.extend_from_slice(b"\nendobj\n");
+		}
+
+		let xref = pdf.len();
+		let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+		for offset in offsets {
+			let _ = writeln!(table, "{:010} 00000 n ", offset);
+		}
+		let _ = write!(
+			table,
+			"trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+			self.objects.len() + 1,
+			root,
+			info,
+			xref
+		);
+		pdf.extend_from_slice(table.as_bytes());
+
+		pdf
+	}
+}
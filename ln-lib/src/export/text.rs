@@ -0,0 +1,291 @@
+//! Markdown and plain text export of a lightnovel
+//!
+//! The files are named by a template like `{novel}/{number:04} - {title}.{ext}` where
+//! `{novel}` is the title of the lightnovel, `{title}` and `{number}` belong to the chapter and
+//! `{ext}` is the extension of the format. A number after the colon pads the value, a leading zero pads with zeros.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error_stack::{Report, Result};
+
+use super::{sanitize_file_name, write_error};
+use crate::err::ExportError;
+use crate::{Lightnovel, LightnovelChapter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+	Markdown,
+	Plain,
+}
+
+impl TextFormat {
+	pub fn extension(&self) -> &str {
+		match self {
+			Self::Markdown => "md",
+			Self::Plain => "txt",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextLayout {
+	FilePerChapter,
+	SingleFile,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+	pub format: TextFormat,
+	pub layout: TextLayout,
+	pub template: Option<String>,
+}
+
+impl Default for TextOptions {
+	fn default() -> Self {
+		Self {
+			format: TextFormat::Markdown,
+			layout: TextLayout::FilePerChapter,
+			template: None,
+		}
+	}
+}
+
+impl TextOptions {
+	pub fn template(&self) -> &str {
+		match (&self.template, self.layout) {
+			(Some(template), _) => template,
+			(None, TextLayout::FilePerChapter) => "{novel}/{number:04} - {title}.{ext}",
+			(None, TextLayout::SingleFile) => "{novel}.{ext}",
+		}
+	}
+}
+
+/// Writes the chapters into the directory and returns the paths of the written files
+pub fn export(ln: &Lightnovel, chapters: &[LightnovelChapter], options: &TextOptions, dir: &Path) -> Result<Vec<PathBuf>, ExportError> {
+	let first = match chapters.first() {
+		Some(first) => first,
+		None => return Err(Report::new(ExportError::NoChapters)),
+	};
+
+	let files = match options.layout {
+		TextLayout::FilePerChapter => chapters
+			.iter()
+			.map(|chapter| {
+				let content = match options.format {
+					TextFormat::Markdown => markdown_chapter(chapter, "#"),
+					TextFormat::Plain => plain_chapter(chapter),
+				};
+				(file_path(dir, options, ln, chapter), content)
+			})
+			.collect::<Vec<_>>(),
+		TextLayout::SingleFile => {
+			let content = match options.format {
+				TextFormat::Markdown => markdown_novel(ln, chapters),
+				TextFormat::Plain => plain_novel(ln, chapters),
+			};
+			vec![(file_path(dir, options, ln, first), content)]
+		}
+	};
+
+	// A template without the number or title of the chapter would write every chapter over the one before
+	let mut seen = HashSet::with_capacity(files.len());
+	if let Some((path, _)) = files.iter().find(|(path, _)| !seen.insert(path)) {
+		let msg = format!(
+			"The template {} gives several chapters the file {}, use {{number}} in it",
+			options.template(),
+			path.display()
+		);
+		return Err(Report::new(ExportError::TemplateError(msg.clone())).attach_printable(msg));
+	}
+
+	let mut paths = Vec::with_capacity(files.len());
+	for (path, content) in files {
+		if let Some(parent) = path.parent() {
+			if fs::create_dir_all(parent).is_err() {
+				return write_error(parent);
+			}
+		}
+		if fs::write(&path, content).is_err() {
+			return write_error(&path);
+		}
+		paths.push(path);
+	}
+
+	Ok(paths)
+}
+
+/// Renders the template for the chapter, every part of the path is a sanitised file name
+pub fn file_path(dir: &Path, options: &TextOptions, ln: &Lightnovel, chapter: &LightnovelChapter) -> PathBuf {
+	let mut path = dir.to_path_buf();
+
+	for part in options.template().split('/').filter(|part| !part.is_empty()) {
+		let part = render(part, |name| match name {
+			"novel" => Some(ln.get_title().clone()),
+			"title" => Some(chapter.get_title().clone()),
+			"number" => Some(chapter.get_chapter_number().to_string()),
+			"ext" => Some(options.format.extension().to_string()),
+			_ => None,
+		});
+		path.push(sanitize_file_name(&part));
+	}
+
+	path
+}
+
+fn render<F: Fn(&str) -> Option<String>>(template: &str, value: F) -> String {
+	let mut rendered = String::with_capacity(template.len());
+	let mut rest = template;
+
+	while let Some(start) = rest.find('{') {
+		rendered.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		let end = match rest.find('}') {
+			Some(end) => end,
+			None => break,
+		};
+
+		let (name, spec) = rest[1..end].split_once(':').unwrap_or((&rest[1..end], ""));
+		match value(name) {
+			Some(value) => {
+				let width = spec.parse::<usize>().unwrap_or(0);
+				if spec.starts_with('0') {
+					let _ = write!(rendered, "{:0>width$}", value, width = width);
+				} else {
+					let _ = write!(rendered, "{:>width$}", value, width = width);
+				}
+			}
+			None => rendered.push_str(&rest[..=end]),
+		}
+
+		rest = &rest[end + 1..];
+	}
+
+	rendered.push_str(rest);
+	rendered
+}
+
+fn markdown_chapter(chapter: &LightnovelChapter, heading: &str) -> String {
+	let mut text = format!("{} {}\n\n", heading, chapter.get_title());
+
+	for paragraph in chapter.iter() {
+		let _ = writeln!(text, "{}\n", paragraph.split('\n').collect::<Vec<_>>().join("  \n"));
+	}
+
+	text
+}
+
+fn markdown_novel(ln: &Lightnovel, chapters: &[LightnovelChapter]) -> String {
+	let details = ln.get_details();
+
+	let mut text = format!("# {}\n\n", ln.get_title());
+	if !details.authors.is_empty() {
+		let _ = writeln!(text, "**Author:** {}  ", details.authors.join(", "));
+	}
+	if !details.genres.is_empty() {
+		let _ = writeln!(text, "**Genres:** {}  ", details.genres.join(", "));
+	}
+	if let Some(status) = &details.status {
+		let _ = writeln!(text, "**Status:** {}  ", status);
+	}
+	text.push('\n');
+	for paragraph in &details.synopsis {
+		let _ = writeln!(text, "> {}\n", paragraph);
+	}
+
+	for chapter in chapters {
+		text.push_str(&markdown_chapter(chapter, "##"));
+	}
+
+	text
+}
+
+fn plain_chapter(chapter: &LightnovelChapter) -> String {
+	let title = chapter.get_title();
+	let mut text = format!("{}\n{}\n\n", title, "=".repeat(title.chars().count()));
+
+	for paragraph in chapter.iter() {
+		let _ = writeln!(text, "{}\n", paragraph);
+	}
+
+	text
+}
+
+fn plain_novel(ln: &Lightnovel, chapters: &[LightnovelChapter]) -> String {
+	let details = ln.get_details();
+
+	let mut text = format!("{}\n\n", ln.get_title());
+	if !details.authors.is_empty() {
+		let _ = writeln!(text, "Author: {}", details.authors.join(", "));
+	}
+	if !details.genres.is_empty() {
+		let _ = writeln!(text, "Genres: {}", details.genres.join(", "));
+	}
+	if let Some(status) = &details.status {
+		let _ = writeln!(text, "Status: {}", status);
+	}
+	text.push('\n');
+	for paragraph in &details.synopsis {
+		let _ = writeln!(text, "{}\n", paragraph);
+	}
+
+	for chapter in chapters {
+		text.push('\n');
+		text.push_str(&plain_chapter(chapter));
+	}
+
+	text
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chapter(number: usize, title: &str) -> LightnovelChapter {
+		let mut chapter = LightnovelChapter::new(title.to_string(), format!("/novel/x/chapter-{}", number), number);
+		chapter.push("Text.".to_string());
+		chapter
+	}
+
+	fn options(template: &str) -> TextOptions {
+		TextOptions {
+			template: Some(template.to_string()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn render_pads_the_values() {
+		let value = |name: &str| match name {
+			"number" => Some("7".to_string()),
+			"title" => Some("Start".to_string()),
+			_ => None,
+		};
+		assert_eq!(render("{number:03} - {title}", value), "007 - Start");
+		assert_eq!(render("{number:3}|{unknown}|{title", value), "  7|{unknown}|{title");
+	}
+
+	#[test]
+	fn file_path_sanitises_every_part() {
+		let ln = Lightnovel::new("Re:Zero".to_string(), "/novel/x".to_string());
+		let path = file_path(Path::new("out"), &options("{novel}/{number:02} {title}.{ext}"), &ln, &chapter(3, "A/B?"));
+		assert_eq!(path, Path::new("out").join("Re_Zero").join("03 A_B_.md"));
+	}
+
+	#[test]
+	fn a_template_which_names_several_chapters_the_same_fails() {
+		let ln = Lightnovel::new("Novel".to_string(), "/novel/x".to_string());
+		let dir = std::env::temp_dir().join(format!("ln-lib-template-{}", std::process::id()));
+		let chapters = [chapter(1, "One"), chapter(2, "Two")];
+
+		assert!(export(&ln, &chapters, &options("{novel}.{ext}"), &dir).is_err());
+		assert!(!dir.exists(), "nothing is written");
+
+		let paths = export(&ln, &chapters, &options("{novel}/{number}.{ext}"), &dir).unwrap();
+		assert_eq!(paths.len(), 2);
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
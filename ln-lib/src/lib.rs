@@ -1,11 +1,15 @@
 mod cfg;
 mod err;
+pub mod export;
 mod ln;
 mod lncat;
 mod lncha;
 mod lnl;
+mod store;
 
-pub use ln::Lightnovel;
+pub use cfg::{http_client, HttpConfig};
+pub use ln::{Lightnovel, LightnovelDetails};
 pub use lncat::LightnovelCategory;
 pub use lncha::LightnovelChapter;
 pub use lnl::LightnovelList;
+pub use store::Store;
@@ -5,11 +5,29 @@ use std::ops::{Deref, DerefMut};
 use std::vec::IntoIter;
 
 use error_stack::Result;
+use serde::{Deserialize, Serialize};
 
 use super::LightnovelChapter;
+use crate::cfg::STORE;
 use crate::err::LightnovelError;
 use scrape::{get_cha, get_cha_by_id};
 
+/// How many chapters a page of the chapter index of the site lists
+pub(crate) const CHAPTERS_PER_PAGE: usize = 48;
+
+/// The information from the page of a lightnovel
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct LightnovelDetails {
+	/// The title on the page of the lightnovel
+	#[serde(default)]
+	pub title: Option<String>,
+	pub authors: Vec<String>,
+	pub genres: Vec<String>,
+	pub status: Option<String>,
+	pub synopsis: Vec<String>,
+	pub cover: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Lightnovel {
 	id: Option<usize>,
@@ -17,6 +35,7 @@ pub struct Lightnovel {
 	url: String,
 	page: usize,
 	last_page: Option<usize>,
+	details: LightnovelDetails,
 	chapters: Vec<LightnovelChapter>,
 }
 
@@ -28,7 +47,8 @@ impl Default for Lightnovel {
 			url: String::new(),
 			page: 1,
 			last_page: None,
-			chapters: Vec::with_capacity(48),
+			details: LightnovelDetails::default(),
+			chapters: Vec::with_capacity(CHAPTERS_PER_PAGE),
 		}
 	}
 }
@@ -62,10 +82,44 @@ impl Lightnovel {
 		self.last_page
 	}
 
+	pub fn get_details(&self) -> &LightnovelDetails {
+		&self.details
+	}
+
 	pub async fn scrape(&mut self) -> Result<(), LightnovelError> {
+		if let Some(store) = STORE.get() {
+			let record = store.load_lightnovel::<LightnovelError>(&self.url)?;
+			let chapters = record.chapters.into_iter().filter(|c| store.has_chapter_file(&c.url)).collect::<Vec<_>>();
+
+			if self.title.is_empty() {
+				self.title = record.title;
+			}
+			self.id = record.id;
+			self.details = record.details;
+			self.last_page = match chapters.len().div_ceil(CHAPTERS_PER_PAGE) {
+				0 | 1 => None,
+				pages => Some(pages),
+			};
+			self.chapters = chapters
+				.into_iter()
+				.skip((self.page - 1) * CHAPTERS_PER_PAGE)
+				.take(CHAPTERS_PER_PAGE)
+				.map(|c| LightnovelChapter::new(c.title, c.url, c.chapter_number))
+				.collect();
+
+			return Ok(());
+		}
+
 		let (id, last_page, mut data) = match self.id {
 			Some(id) => (id, self.last_page, get_cha_by_id(id, self.page).await?),
-			None => get_cha(&self.url, None).await?,
+			None => {
+				let (id, last_page, details, data) = get_cha(&self.url, None).await?;
+				if self.title.is_empty() {
+					self.title = details.title.clone().unwrap_or_default();
+				}
+				self.details = details;
+				(id, last_page, data)
+			}
 		};
 
 		self.id = Some(id);
@@ -74,12 +128,26 @@ impl Lightnovel {
 		self.chapters = data
 			.iter_mut()
 			.enumerate()
-			.map(|(i, (title, url))| LightnovelChapter::new(mem::take(title), mem::take(url), (self.page - 1) * 48 + i + 1))
+			.map(|(i, (title, url))| LightnovelChapter::new(mem::take(title), mem::take(url), (self.page - 1) * CHAPTERS_PER_PAGE + i + 1))
 			.collect();
 
 		Ok(())
 	}
 
+	/// Scrapes every page and returns the whole chapter index
+	pub async fn scrape_index(&mut self) -> Result<Vec<LightnovelChapter>, LightnovelError> {
+		self.page = 1;
+		self.scrape().await?;
+
+		let mut chapters = self.chapters.clone();
+		while self.next_page().is_some() {
+			self.scrape().await?;
+			chapters.extend(self.chapters.iter().cloned());
+		}
+
+		Ok(chapters)
+	}
+
 	pub async fn next_scrape(&mut self) -> Result<bool, LightnovelError> {
 		if let None = self.next_page() {
 			return Ok(false);
@@ -108,6 +176,11 @@ impl Lightnovel {
 		self.open_page(self.page + 1)
 	}
 
+	/// The page of the chapter index which lists the chapter with the number, the chapters are numbered from 1
+	pub fn page_of(number: usize) -> usize {
+		number.saturating_sub(1) / CHAPTERS_PER_PAGE + 1
+	}
+
 	pub fn open_page(&mut self, page: usize) -> Option<usize> {
 		if page <= self.last_page.unwrap_or(1) && page >= 1 {
 			self.page = page;
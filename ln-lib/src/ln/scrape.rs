@@ -2,7 +2,8 @@ use error_stack::{Report, Result, ResultExt};
 use scraper::{Html, Selector};
 use surf::http::convert::{Deserialize, Serialize};
 
-use crate::cfg::CLIENT;
+use super::LightnovelDetails;
+use crate::cfg::{client, Page};
 use crate::err::{LightnovelError, SurfError};
 
 #[derive(Deserialize, Serialize)]
@@ -38,12 +39,66 @@ fn get_last_page(document: &Html) -> Option<usize> {
 	}
 }
 
-pub async fn get_cha(url: &String, page: Option<usize>) -> Result<(usize, Option<usize>, Vec<(String, String)>), LightnovelError> {
-	let client = CLIENT.get().unwrap();
+fn get_details(document: &Html) -> LightnovelDetails {
+	let info_selector = Selector::parse("div.info>div").unwrap();
+	let label_selector = Selector::parse("h3").unwrap();
+	let link_selector = Selector::parse("a").unwrap();
+	let desc_selector = Selector::parse("div.desc-text>p").unwrap();
+	let cover_selector = Selector::parse("div.book>img").unwrap();
+	let title_selector = Selector::parse("h3.title").unwrap();
+
+	let title = document
+		.select(&title_selector)
+		.next()
+		.map(|title| title.text().collect::<String>().trim().to_string())
+		.filter(|title| !title.is_empty());
+
+	let mut details = LightnovelDetails { title, ..Default::default() };
+
+	for info in document.select(&info_selector) {
+		let label = match info.select(&label_selector).next() {
+			Some(label) => label.text().collect::<String>(),
+			None => continue,
+		};
+		let links = info
+			.select(&link_selector)
+			.map(|a| a.text().collect::<String>().trim().to_string())
+			.filter(|text| !text.is_empty())
+			.collect::<Vec<_>>();
+
+		match label.trim().trim_end_matches(':').to_lowercase().as_str() {
+			"author" | "authors" => details.authors = links,
+			"genre" | "genres" => details.genres = links,
+			"status" => {
+				let status = info.text().collect::<String>().replacen(&label, "", 1);
+				details.status = Some(status.trim().to_string()).filter(|status| !status.is_empty());
+			}
+			_ => {}
+		}
+	}
+
+	details.synopsis = document
+		.select(&desc_selector)
+		.map(|p| p.text().collect::<String>().trim().to_string())
+		.filter(|p| !p.is_empty())
+		.collect();
+
+	details.cover = document
+		.select(&cover_selector)
+		.next()
+		.and_then(|img| img.value().attr("data-src").or_else(|| img.value().attr("src")))
+		.map(|src| src.to_string());
+
+	details
+}
+
+pub async fn get_cha(url: &String, page: Option<usize>) -> Result<(usize, Option<usize>, LightnovelDetails, Vec<(String, String)>), LightnovelError> {
+	let client = client().change_context(SurfError::ClientCreationError.into())?;
 
-	let req = client.get(url);
+	let mut req = client.get(url).build();
+	req.set_ext(Page::Index);
 
-	let mut res = match req.send().await {
+	let mut res = match client.send(req).await {
 		Ok(res) => res,
 		Err(_) => {
 			let msg = format!("There was a problem while with sending the requet to: {}", url);
@@ -60,30 +115,39 @@ pub async fn get_cha(url: &String, page: Option<usize>) -> Result<(usize, Option
 		}
 	};
 
-	let document = Html::parse_document(&res_body);
+	// The document can't be held across an await since it isn't Send
+	let (id, last_page, details, first_chapters) = {
+		let document = Html::parse_document(&res_body);
 
-	let id = get_id(&document)
-    .attach_printable(format!("There was a problem with getting the id from: {}", url))?;
+		let id = get_id(&document).attach_printable(format!("There was a problem with getting the id from: {}", url))?;
 
-	let last_page = get_last_page(&document);
+		let first_chapters = match page {
+			Some(1) | None => Some(parse_html(&document)),
+			Some(_) => None,
+		};
 
-	let chapters = match page {
-		Some(1) | None => parse_html(&document),
-		Some(p) => get_cha_by_id(id, p).await?,
+		(id, get_last_page(&document), get_details(&document), first_chapters)
 	};
 
-	Ok((id, last_page, chapters))
+	let chapters = match first_chapters {
+		Some(chapters) => chapters,
+		None => get_cha_by_id(id, page.unwrap_or(1)).await?,
+	};
+
+	Ok((id, last_page, details, chapters))
 }
 
 pub async fn get_cha_by_id(id: usize, page: usize) -> Result<Vec<(String, String)>, LightnovelError> {
-	let client = CLIENT.get().unwrap();
+	let client = client().change_context(SurfError::ClientCreationError.into())?;
 
-	let mut res = match client
+	let mut req = client
 		.post("/wp-admin/admin-ajax.php")
 		.header("content-type", "application/x-www-form-urlencoded")
 		.body_string(format!("action=tw_ajax&type=pagination&id={}&page={}", id, page))
-		.await
-	{
+		.build();
+	req.set_ext(Page::Index);
+
+	let mut res = match client.send(req).await {
 		Ok(res) => res,
 		Err(_) => {
 			let msg = format!(
@@ -5,6 +5,7 @@ use std::vec::IntoIter;
 
 use error_stack::Result;
 
+use crate::cfg::STORE;
 use crate::err::ChapterError;
 
 #[derive(Default, Debug, Clone)]
@@ -42,7 +43,10 @@ impl LightnovelChapter {
 	}
 
 	pub async fn scrape(&mut self) -> Result<bool, ChapterError> {
-		self.paragraph = scrape::get_paragraph(&self.url).await?;
+		self.paragraph = match STORE.get() {
+			Some(store) => store.read_chapter::<ChapterError>(&self.url)?,
+			None => scrape::get_paragraph(&self.url).await?,
+		};
 		Ok(!self.paragraph.is_empty())
 	}
 }
@@ -1,10 +1,16 @@
-use error_stack::{Report, Result};
+use error_stack::{Report, Result, ResultExt};
 use scraper::{Html, Selector};
 
+use crate::cfg::{client, Page};
 use crate::err::{ChapterError, SurfError};
 
 pub async fn get_paragraph(url: &String) -> Result<Vec<String>, ChapterError> {
-	let mut res = match surf::get(url).send().await {
+	let client = client().change_context(SurfError::ClientCreationError.into())?;
+
+	let mut req = client.get(url).build();
+	req.set_ext(Page::Chapter);
+
+	let mut res = match client.send(req).await {
 		Ok(res) => res,
 		Err(_) => {
 			let msg = format!("There was a problem while with sending the requet to: {}", url);
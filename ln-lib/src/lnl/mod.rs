@@ -1,16 +1,16 @@
 mod scrape;
 
+use std::cmp::Reverse;
 use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::vec::IntoIter;
 
-use error_stack::{IntoReport, Result, ResultExt};
-use surf::{middleware::Redirect, Client, Config, Url};
+use error_stack::{Report, Result, ResultExt};
 
 use super::Lightnovel;
 use super::LightnovelCategory;
-use crate::cfg::{CLIENT, LIGHTNOVEL_SITE};
-use crate::err::{ListError, SurfError};
+use crate::cfg::{client, STORE};
+use crate::err::{ListError, StoreError, SurfError};
 use scrape::get_ln;
 
 #[derive(Debug)]
@@ -34,16 +34,7 @@ impl Default for LightnovelList {
 
 impl LightnovelList {
 	pub fn new(category: LightnovelCategory) -> Result<Self, ListError> {
-		if let None = CLIENT.get() {
-			let url = Url::parse(LIGHTNOVEL_SITE)
-				.into_report()
-				.change_context(SurfError::UriParserError.into())?;
-			let config = Config::new().set_base_url(url);
-
-			let client: Client = config.try_into().into_report().change_context(SurfError::ClientCreationError.into())?;
-
-			CLIENT.set(client.with(Redirect::new(3))).unwrap();
-		}
+		client().change_context(SurfError::ClientCreationError.into())?;
 
 		Ok(Self {
 			category,
@@ -66,6 +57,48 @@ impl LightnovelList {
 	pub async fn scrape(&mut self) -> Result<(), ListError> {
 		use LightnovelCategory::*;
 
+		if let Some(store) = STORE.get() {
+			let mut records = store
+				.load_lightnovels::<ListError>()?
+				.into_iter()
+				.filter(|record| match &self.category {
+					Title(t) => record.title.to_lowercase().contains(&t.to_lowercase()),
+					Genre(g) => record.details.genres.iter().any(|genre| same_genre(genre, g)),
+					Completed => record
+						.details
+						.status
+						.as_deref()
+						.map(|status| status.eq_ignore_ascii_case("completed"))
+						.unwrap_or(false),
+					Latest => true,
+				})
+				.collect::<Vec<_>>();
+
+			// The latest are the lightnovels whose chapter index changed last
+			if let Latest = self.category {
+				records.sort_by_key(|record| Reverse(store.modified(&record.url)));
+			}
+
+			if records.is_empty() {
+				let msg = format!("There are no downloaded lightnovels in: {}", store.root().display());
+				let report = Report::new(StoreError::NotDownloaded(format!("A lightnovel matching {}", self.category)).into());
+				return Err(report.attach_printable(msg));
+			}
+
+			self.last_page = match records.len().div_ceil(24) {
+				0 | 1 => None,
+				pages => Some(pages),
+			};
+			self.list = records
+				.into_iter()
+				.skip((self.page - 1) * 24)
+				.take(24)
+				.map(|record| Lightnovel::new(record.title, record.url))
+				.collect();
+
+			return Ok(());
+		}
+
 		let url = match &self.category {
 			Latest => format!("/latest/page/{}", self.page),
 			Completed => format!("/completed/page/{}", self.page),
@@ -148,3 +181,15 @@ impl DerefMut for LightnovelList {
 		&mut self.list
 	}
 }
+
+/// If the genre of a lightnovel like `Martial Arts` is the genre of the url like `martial-arts`
+fn same_genre(genre: &str, category: &str) -> bool {
+	let simple = |text: &str| {
+		text
+			.chars()
+			.filter(|c| c.is_alphanumeric())
+			.flat_map(char::to_lowercase)
+			.collect::<String>()
+	};
+	simple(genre) == simple(category.rsplit('/').next().unwrap_or(category))
+}
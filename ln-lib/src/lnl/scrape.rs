@@ -1,15 +1,16 @@
-use error_stack::{Report, Result};
+use error_stack::{Report, Result, ResultExt};
 use scraper::{Html, Selector};
 
-use crate::cfg::{CLIENT, LIGHTNOVEL_SITE};
+use crate::cfg::{client, site, Page};
 use crate::err::{ListError, SurfError};
 
 pub async fn get_ln(url: &String) -> Result<(Vec<(String, String)>, Option<usize>), ListError> {
-	let client = CLIENT.get().unwrap();
+	let client = client().change_context(SurfError::ClientCreationError.into())?;
 
-	let req = client.get(url);
+	let mut req = client.get(url).build();
+	req.set_ext(Page::Index);
 
-	let mut res = match req.send().await {
+	let mut res = match client.send(req).await {
 		Ok(res) => res,
 		Err(_) => {
 			let msg = format!("There was a problem while with sending the requet to: {}", url);
@@ -37,7 +38,7 @@ pub async fn get_ln(url: &String) -> Result<(Vec<(String, String)>, Option<usize
 			let href = a.value().attr("href").unwrap();
 			let title = a.value().attr("title").unwrap();
 
-			(title.to_string(), href.to_string().replace(LIGHTNOVEL_SITE, ""))
+			(title.to_string(), href.to_string().replace(site(), ""))
 		})
 		.collect();
 
@@ -0,0 +1,353 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use error_stack::{Context, Report, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::{Lightnovel, LightnovelChapter, LightnovelDetails};
+use crate::cfg::{fnv, site, STORE};
+use crate::err::StoreError;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct LightnovelRecord {
+	pub title: String,
+	pub url: String,
+	pub id: Option<usize>,
+	#[serde(default)]
+	pub details: LightnovelDetails,
+	pub chapters: Vec<ChapterRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ChapterRecord {
+	pub title: String,
+	pub url: String,
+	pub chapter_number: usize,
+}
+
+/// A local copy of downloaded lightnovels and chapters
+///
+/// The store is a directory with a `novels` folder holding the chapter index of every lightnovel
+/// and a `chapters` folder holding the paragraphs of every downloaded chapter.
+#[derive(Debug, Clone)]
+pub struct Store {
+	root: PathBuf,
+}
+
+impl Store {
+	pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+		Self { root: root.into() }
+	}
+
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	/// Makes `LightnovelList`, `Lightnovel` and `LightnovelChapter` resolve only from this store
+	pub fn go_offline(self) -> bool {
+		STORE.set(self).is_ok()
+	}
+
+	pub fn is_offline() -> bool {
+		STORE.get().is_some()
+	}
+
+	pub fn has_lightnovel(&self, url: &str) -> bool {
+		self.novel_path(url).exists()
+	}
+
+	/// If the chapter was downloaded completely, a file which was cut off or has no paragraphs doesn't count
+	pub fn has_chapter(&self, url: &str) -> bool {
+		self
+			.read_chapter::<StoreError>(url)
+			.map(|paragraphs| !paragraphs.is_empty())
+			.unwrap_or(false)
+	}
+
+	/// If there is a file with paragraphs for the chapter, it only looks at the size of the file so it is cheap
+	/// enough for listing the chapters but it doesn't notice a broken file like `has_chapter` does
+	pub(crate) fn has_chapter_file(&self, url: &str) -> bool {
+		fs::metadata(self.chapter_path(url))
+			.map(|metadata| metadata.is_file() && metadata.len() > "[]".len() as u64)
+			.unwrap_or(false)
+	}
+
+	/// Adds the chapters to the stored index of the lightnovel
+	pub fn save_lightnovel(&self, ln: &Lightnovel, chapters: &[LightnovelChapter]) -> Result<(), StoreError> {
+		let path = self.novel_path(ln.get_url());
+
+		let mut record = match read::<LightnovelRecord, StoreError>(&path, ln.get_title()) {
+			Ok(record) => record,
+			Err(_) => LightnovelRecord {
+				title: ln.get_title().clone(),
+				url: ln.get_url().clone(),
+				..Default::default()
+			},
+		};
+
+		record.id = ln.get_id().or(record.id);
+		if ln.get_id().is_some() {
+			record.details = ln.get_details().clone();
+		}
+		for chapter in chapters {
+			record.chapters.retain(|c| path_of(&c.url) != path_of(chapter.get_url()));
+			record.chapters.push(ChapterRecord {
+				title: chapter.get_title().clone(),
+				url: chapter.get_url().clone(),
+				chapter_number: chapter.get_chapter_number(),
+			});
+		}
+		record.chapters.sort_by_key(|c| c.chapter_number);
+
+		write(&path, &record)
+	}
+
+	pub fn save_chapter(&self, chapter: &LightnovelChapter) -> Result<(), StoreError> {
+		write(&self.chapter_path(chapter.get_url()), &chapter[..])
+	}
+
+	/// The downloaded lightnovels sorted by title
+	pub fn lightnovels(&self) -> Result<Vec<Lightnovel>, StoreError> {
+		let records = self.load_lightnovels::<StoreError>()?;
+		Ok(records.into_iter().map(|record| Lightnovel::new(record.title, record.url)).collect())
+	}
+
+	/// The stored chapter index of the lightnovel, the chapters are empty until they are loaded
+	pub fn chapters(&self, url: &str) -> Result<Vec<LightnovelChapter>, StoreError> {
+		let record = self.load_lightnovel::<StoreError>(url)?;
+		Ok(
+			record
+				.chapters
+				.into_iter()
+				.map(|chapter| LightnovelChapter::new(chapter.title, chapter.url, chapter.chapter_number))
+				.collect(),
+		)
+	}
+
+	/// Deletes the index and the chapters of the lightnovel and returns how many chapters were deleted
+	pub fn remove_lightnovel(&self, url: &str) -> Result<usize, StoreError> {
+		let record = self.load_lightnovel::<StoreError>(url)?;
+
+		let mut removed = 0;
+		for chapter in &record.chapters {
+			let path = self.chapter_path(&chapter.url);
+			if path.exists() {
+				remove(&path)?;
+				removed += 1;
+			}
+		}
+		remove(&self.novel_path(url))?;
+
+		Ok(removed)
+	}
+
+	/// Deletes everything that was downloaded
+	pub fn clear(&self) -> Result<(), StoreError> {
+		for dir in ["novels", "chapters"] {
+			let path = self.root.join(dir);
+			if path.exists() && fs::remove_dir_all(&path).is_err() {
+				let msg = format!("There was a problem with deleting: {}", path.display());
+				return Err(Report::new(StoreError::WriteError(msg.clone())).attach_printable(msg));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// The size of the downloaded files in bytes
+	pub fn size(&self) -> u64 {
+		["novels", "chapters"]
+			.iter()
+			.filter_map(|dir| fs::read_dir(self.root.join(dir)).ok())
+			.flat_map(|entries| entries.flatten())
+			.filter_map(|entry| entry.metadata().ok())
+			.map(|metadata| metadata.len())
+			.sum()
+	}
+
+	pub(crate) fn load_lightnovels<E: Context + From<StoreError>>(&self) -> Result<Vec<LightnovelRecord>, E> {
+		let entries = match fs::read_dir(self.root.join("novels")) {
+			Ok(entries) => entries,
+			Err(_) => return Ok(Vec::new()),
+		};
+
+		let mut records = Vec::new();
+		for entry in entries.flatten() {
+			let path = entry.path();
+			if path.extension().map(|ext| ext == "json").unwrap_or(false) {
+				records.push(read(&path, &path.display().to_string())?);
+			}
+		}
+		records.sort_by(|a: &LightnovelRecord, b| a.title.cmp(&b.title));
+
+		Ok(records)
+	}
+
+	pub(crate) fn load_lightnovel<E: Context + From<StoreError>>(&self, url: &str) -> Result<LightnovelRecord, E> {
+		read(&self.novel_path(url), &format!("The lightnovel {}", url))
+	}
+
+	/// When the stored index of the lightnovel was changed the last time
+	pub(crate) fn modified(&self, url: &str) -> Option<SystemTime> {
+		fs::metadata(self.novel_path(url)).and_then(|metadata| metadata.modified()).ok()
+	}
+
+	/// Fills the chapter with its downloaded paragraphs, returns if the chapter has any
+	pub fn load_chapter(&self, chapter: &mut LightnovelChapter) -> Result<bool, StoreError> {
+		let paragraphs = self.read_chapter::<StoreError>(chapter.get_url())?;
+		chapter.clear();
+		chapter.extend(paragraphs);
+		Ok(!chapter.is_empty())
+	}
+
+	pub(crate) fn read_chapter<E: Context + From<StoreError>>(&self, url: &str) -> Result<Vec<String>, E> {
+		read(&self.chapter_path(url), &format!("The chapter {}", url))
+	}
+
+	fn novel_path(&self, url: &str) -> PathBuf {
+		self.root.join("novels").join(format!("{}.json", slug(url)))
+	}
+
+	fn chapter_path(&self, url: &str) -> PathBuf {
+		self.root.join("chapters").join(format!("{}.json", slug(url)))
+	}
+}
+
+/// The url without the site, so relative and absolute urls of a page are the same
+fn path_of(url: &str) -> &str {
+	url
+		.trim_start_matches(site())
+		.trim_start_matches("https://")
+		.trim_start_matches("http://")
+}
+
+/// The file name of the url, the readable part is followed by a hash of the url
+/// so urls which only differ in their punctuation don't share a file
+fn slug(url: &str) -> String {
+	let readable = path_of(url)
+		.split(|c: char| !c.is_ascii_alphanumeric())
+		.filter(|part| !part.is_empty())
+		.collect::<Vec<_>>()
+		.join("-");
+
+	format!("{}-{:016x}", readable, fnv(path_of(url)))
+}
+
+fn read<T: DeserializeOwned, E: Context + From<StoreError>>(path: &Path, what: &str) -> Result<T, E> {
+	let content = match fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(err) if err.kind() == ErrorKind::NotFound => {
+			let msg = format!("There is no file in the store at: {}", path.display());
+			let report = Report::new(StoreError::NotDownloaded(what.to_string()).into());
+			return Err(report.attach_printable(msg));
+		}
+		Err(_) => {
+			let msg = format!("There was a problem with reading the file: {}", path.display());
+			let report = Report::new(StoreError::ReadError(msg.clone()).into());
+			return Err(report.attach_printable(msg));
+		}
+	};
+
+	match serde_json::from_str(&content) {
+		Ok(value) => Ok(value),
+		Err(_) => {
+			let msg = format!("There was a problem with parsing the file: {}", path.display());
+			let report = Report::new(StoreError::ParseError(msg.clone()).into());
+			Err(report.attach_printable(msg))
+		}
+	}
+}
+
+fn write<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), StoreError> {
+	let msg = format!("There was a problem with writing the file: {}", path.display());
+
+	let content = match serde_json::to_string(value) {
+		Ok(content) => content,
+		Err(_) => return Err(Report::new(StoreError::WriteError(msg.clone())).attach_printable(msg)),
+	};
+
+	if let Some(parent) = path.parent() {
+		if fs::create_dir_all(parent).is_err() {
+			return Err(Report::new(StoreError::WriteError(msg.clone())).attach_printable(msg));
+		}
+	}
+
+	// The file is written next to its place and then renamed, so a download which is killed never leaves half a file
+	static WRITES: AtomicUsize = AtomicUsize::new(0);
+	let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+	let temp = path.with_file_name(format!(".{}.{}-{}.tmp", name, process::id(), WRITES.fetch_add(1, Ordering::Relaxed)));
+
+	match fs::write(&temp, content).and_then(|()| fs::rename(&temp, path)) {
+		Ok(()) => Ok(()),
+		Err(_) => {
+			let _ = fs::remove_file(&temp);
+			Err(Report::new(StoreError::WriteError(msg.clone())).attach_printable(msg))
+		}
+	}
+}
+
+fn remove(path: &Path) -> Result<(), StoreError> {
+	match fs::remove_file(path) {
+		Ok(()) => Ok(()),
+		Err(_) => {
+			let msg = format!("There was a problem with deleting the file: {}", path.display());
+			Err(Report::new(StoreError::WriteError(msg.clone())).attach_printable(msg))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slug_is_readable() {
+		assert!(slug("/novel/the-novel/chapter-12").starts_with("novel-the-novel-chapter-12-"));
+	}
+
+	#[test]
+	fn slug_of_a_relative_and_an_absolute_url_is_the_same() {
+		let absolute = format!("{}/novel/the-novel", site());
+		assert_eq!(slug(&absolute), slug("/novel/the-novel"));
+	}
+
+	#[test]
+	fn slugs_of_urls_which_differ_in_punctuation_differ() {
+		assert_ne!(slug("/novel/a-b"), slug("/novel/a_b"));
+	}
+
+	fn temp_store(name: &str) -> (Store, PathBuf) {
+		let root = std::env::temp_dir().join(format!("ln-lib-{}-{}", name, process::id()));
+		(Store::new(&root), root)
+	}
+
+	#[test]
+	fn cut_off_and_empty_chapters_are_not_downloaded() {
+		let (store, root) = temp_store("cut-off");
+		let mut chapter = LightnovelChapter::new("Chapter 1".to_string(), "/novel/x/chapter-1".to_string(), 1);
+		assert!(!store.has_chapter(chapter.get_url()));
+
+		store.save_chapter(&chapter).unwrap();
+		assert!(!store.has_chapter(chapter.get_url()));
+
+		assert!(!store.has_chapter_file(chapter.get_url()));
+
+		chapter.push("Text.".to_string());
+		store.save_chapter(&chapter).unwrap();
+		assert!(store.has_chapter(chapter.get_url()));
+		assert!(store.has_chapter_file(chapter.get_url()));
+
+		fs::write(store.chapter_path(chapter.get_url()), "[\"Te").unwrap();
+		assert!(!store.has_chapter(chapter.get_url()));
+
+		let files = fs::read_dir(root.join("chapters")).unwrap().count();
+		assert_eq!(files, 1, "no temporary files are left");
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}